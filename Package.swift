@@ -0,0 +1,30 @@
+// swift-tools-version:5.3
+//
+// Swift wrapper around the LibreAuth C bindings. The library and its header
+// must have been installed beforehand (see bindings/swift/README.md).
+
+import PackageDescription
+
+let package = Package(
+    name: "LibreAuth",
+    products: [
+        .library(name: "LibreAuth", targets: ["LibreAuth"]),
+    ],
+    targets: [
+        .systemLibrary(
+            name: "CLibreAuth",
+            path: "bindings/swift/Sources/CLibreAuth",
+            pkgConfig: "libreauth"
+        ),
+        .target(
+            name: "LibreAuth",
+            dependencies: ["CLibreAuth"],
+            path: "bindings/swift/Sources/LibreAuth"
+        ),
+        .testTarget(
+            name: "LibreAuthTests",
+            dependencies: ["LibreAuth"],
+            path: "bindings/swift/Tests/LibreAuthTests"
+        ),
+    ]
+)
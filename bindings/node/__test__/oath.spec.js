@@ -0,0 +1,60 @@
+const { HOTPBuilder, TOTPBuilder } = require('../index')
+
+const KEYS = {
+  sha1: '12345678901234567890',
+  sha256: '12345678901234567890123456789012',
+  sha512: '1234567890123456789012345678901234567890123456789012345678901234',
+}
+
+const RFC6238_VECTORS = [
+  [59, '94287082', '46119246', '90693936'],
+  [1111111109, '07081804', '68084774', '25091201'],
+  [1111111111, '14050471', '67062674', '99943326'],
+  [1234567890, '89005924', '91819424', '93441116'],
+  [2000000000, '69279037', '90698825', '38618901'],
+  [20000000000, '65353130', '77737706', '47863826'],
+]
+
+const RFC4226_CODES = [
+  '755224', '287082', '359152', '969429', '338314',
+  '254676', '287922', '162583', '399871', '520489',
+]
+
+describe('TOTP', () => {
+  test.each(RFC6238_VECTORS)('RFC 6238 vectors at %i', (timestamp, ...codes) => {
+    Object.entries(KEYS).forEach(([hashFunction, key], i) => {
+      const totp = new TOTPBuilder()
+        .asciiKey(key)
+        .timestamp(timestamp)
+        .outputLen(8)
+        .hashFunction(hashFunction)
+        .finalize()
+      expect(totp.generate()).toBe(codes[i])
+      expect(totp.isValid(codes[i])).toBe(true)
+      expect(totp.isValid('12345678')).toBe(false)
+    })
+  })
+
+  test('invalid configuration', () => {
+    expect(() => new TOTPBuilder().base32Key('1').finalize()).toThrow('InvalidKey')
+    expect(() => new TOTPBuilder().asciiKey(KEYS.sha1).period(0).finalize()).toThrow('InvalidPeriod')
+    expect(() => new TOTPBuilder().hashFunction('md5')).toThrow()
+  })
+})
+
+describe('HOTP', () => {
+  test('RFC 4226 vectors', () => {
+    const hotp = new HOTPBuilder().key(Buffer.from(KEYS.sha1)).finalize()
+    RFC4226_CODES.forEach((code) => {
+      expect(hotp.generate()).toBe(code)
+      expect(hotp.isValid(code)).toBe(true)
+      hotp.incrementCounter()
+    })
+  })
+
+  test('counter', () => {
+    const hotp = new HOTPBuilder().asciiKey(KEYS.sha1).counter(9).finalize()
+    expect(hotp.generate()).toBe('520489')
+    expect(() => new HOTPBuilder().counter(-1)).toThrow()
+  })
+})
@@ -0,0 +1,266 @@
+//! Node.js bindings for the HOTP and TOTP parts of LibreAuth.
+//!
+//! The builders keep the same methods as their Rust counterparts, in camel case. Errors are thrown
+//! as JavaScript exceptions whose message is the name of the underlying `ErrorCode`.
+
+use libreauth::hash::HashFunction;
+use libreauth::oath;
+use napi::bindgen_prelude::Buffer;
+use napi::{Error, Result};
+use napi_derive::napi;
+
+fn to_js_err(error: oath::ErrorCode) -> Error {
+    Error::from_reason(format!("{:?}", error))
+}
+
+fn parse_hash_function(hash_function: &str) -> Result<HashFunction> {
+    hash_function
+        .parse()
+        .map_err(|_| Error::from_reason(format!("{}: unsupported hash function", hash_function)))
+}
+
+/// Builds an HOTP object.
+#[napi(js_name = "HOTPBuilder")]
+pub struct HOTPBuilder {
+    inner: oath::HOTPBuilder,
+}
+
+impl Default for HOTPBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[napi]
+impl HOTPBuilder {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        HOTPBuilder {
+            inner: oath::HOTPBuilder::new(),
+        }
+    }
+
+    #[napi]
+    pub fn key(&mut self, key: Buffer) -> &Self {
+        self.inner.key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn ascii_key(&mut self, key: String) -> &Self {
+        self.inner.ascii_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn hex_key(&mut self, key: String) -> &Self {
+        self.inner.hex_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn base32_key(&mut self, key: String) -> &Self {
+        self.inner.base32_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn base64_key(&mut self, key: String) -> &Self {
+        self.inner.base64_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn output_len(&mut self, output_len: u32) -> &Self {
+        self.inner.output_len(output_len as usize);
+        self
+    }
+
+    #[napi]
+    pub fn output_base(&mut self, base: String) -> &Self {
+        self.inner.output_base(&base);
+        self
+    }
+
+    #[napi]
+    pub fn hash_function(&mut self, hash_function: String) -> Result<&Self> {
+        self.inner
+            .hash_function(parse_hash_function(&hash_function)?);
+        Ok(self)
+    }
+
+    #[napi]
+    pub fn counter(&mut self, counter: i64) -> Result<&Self> {
+        if counter < 0 {
+            return Err(Error::from_reason("the counter cannot be negative"));
+        }
+        self.inner.counter(counter as u64);
+        Ok(self)
+    }
+
+    #[napi]
+    pub fn finalize(&self) -> Result<HOTP> {
+        let inner = self.inner.finalize().map_err(to_js_err)?;
+        Ok(HOTP { inner })
+    }
+}
+
+/// Generates, manipulates and checks HOTP codes.
+#[napi(js_name = "HOTP")]
+pub struct HOTP {
+    inner: oath::HOTP,
+}
+
+#[napi]
+impl HOTP {
+    #[napi]
+    pub fn generate(&self) -> String {
+        self.inner.generate()
+    }
+
+    #[napi]
+    pub fn increment_counter(&mut self) -> &Self {
+        self.inner.increment_counter();
+        self
+    }
+
+    #[napi]
+    pub fn is_valid(&self, code: String) -> bool {
+        self.inner.is_valid(&code)
+    }
+}
+
+/// Builds a TOTP object.
+#[napi(js_name = "TOTPBuilder")]
+pub struct TOTPBuilder {
+    inner: oath::TOTPBuilder,
+}
+
+impl Default for TOTPBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[napi]
+impl TOTPBuilder {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        TOTPBuilder {
+            inner: oath::TOTPBuilder::new(),
+        }
+    }
+
+    #[napi]
+    pub fn key(&mut self, key: Buffer) -> &Self {
+        self.inner.key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn ascii_key(&mut self, key: String) -> &Self {
+        self.inner.ascii_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn hex_key(&mut self, key: String) -> &Self {
+        self.inner.hex_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn base32_key(&mut self, key: String) -> &Self {
+        self.inner.base32_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn base64_key(&mut self, key: String) -> &Self {
+        self.inner.base64_key(&key);
+        self
+    }
+
+    #[napi]
+    pub fn output_len(&mut self, output_len: u32) -> &Self {
+        self.inner.output_len(output_len as usize);
+        self
+    }
+
+    #[napi]
+    pub fn output_base(&mut self, base: String) -> &Self {
+        self.inner.output_base(&base);
+        self
+    }
+
+    #[napi]
+    pub fn hash_function(&mut self, hash_function: String) -> Result<&Self> {
+        self.inner
+            .hash_function(parse_hash_function(&hash_function)?);
+        Ok(self)
+    }
+
+    #[napi]
+    pub fn timestamp(&mut self, timestamp: i64) -> &Self {
+        self.inner.timestamp(timestamp);
+        self
+    }
+
+    #[napi]
+    pub fn tolerance(&mut self, tolerance: u32) -> &Self {
+        self.inner.tolerance(u64::from(tolerance));
+        self
+    }
+
+    #[napi]
+    pub fn positive_tolerance(&mut self, tolerance: u32) -> &Self {
+        self.inner.positive_tolerance(u64::from(tolerance));
+        self
+    }
+
+    #[napi]
+    pub fn negative_tolerance(&mut self, tolerance: u32) -> &Self {
+        self.inner.negative_tolerance(u64::from(tolerance));
+        self
+    }
+
+    #[napi]
+    pub fn period(&mut self, period: u32) -> &Self {
+        self.inner.period(period);
+        self
+    }
+
+    #[napi]
+    pub fn initial_time(&mut self, initial_time: i64) -> Result<&Self> {
+        if initial_time < 0 {
+            return Err(Error::from_reason("the initial time cannot be negative"));
+        }
+        self.inner.initial_time(initial_time as u64);
+        Ok(self)
+    }
+
+    #[napi]
+    pub fn finalize(&self) -> Result<TOTP> {
+        let inner = self.inner.finalize().map_err(to_js_err)?;
+        Ok(TOTP { inner })
+    }
+}
+
+/// Generates and checks TOTP codes.
+#[napi(js_name = "TOTP")]
+pub struct TOTP {
+    inner: oath::TOTP,
+}
+
+#[napi]
+impl TOTP {
+    #[napi]
+    pub fn generate(&self) -> String {
+        self.inner.generate()
+    }
+
+    #[napi]
+    pub fn is_valid(&self, code: String) -> bool {
+        self.inner.is_valid(&code)
+    }
+}
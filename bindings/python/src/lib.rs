@@ -0,0 +1,96 @@
+//! Python bindings for the HOTP and TOTP parts of LibreAuth.
+//!
+//! Errors are raised as `ValueError` exceptions whose message is the name of the underlying
+//! `ErrorCode`.
+
+use libreauth::hash::HashFunction;
+use libreauth::oath::{ErrorCode, HOTPBuilder, TOTPBuilder, HOTP, TOTP};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+fn to_py_err(error: ErrorCode) -> PyErr {
+    PyValueError::new_err(format!("{:?}", error))
+}
+
+fn parse_algorithm(algorithm: &str) -> PyResult<HashFunction> {
+    algorithm
+        .parse()
+        .map_err(|_| PyValueError::new_err(format!("{}: unsupported algorithm", algorithm)))
+}
+
+/// Generates and checks TOTP codes.
+#[pyclass]
+struct TOTPGenerator {
+    inner: TOTP,
+}
+
+#[pymethods]
+impl TOTPGenerator {
+    #[new]
+    #[pyo3(signature = (key_b32, period = 30, digits = 6, algorithm = "sha1"))]
+    fn new(key_b32: &str, period: u32, digits: usize, algorithm: &str) -> PyResult<Self> {
+        let inner = TOTPBuilder::new()
+            .base32_key(key_b32)
+            .period(period)
+            .output_len(digits)
+            .hash_function(parse_algorithm(algorithm)?)
+            .finalize()
+            .map_err(to_py_err)?;
+        Ok(TOTPGenerator { inner })
+    }
+
+    /// Generate the current TOTP value.
+    fn generate(&self) -> String {
+        self.inner.generate()
+    }
+
+    /// Checks if the given code is valid.
+    fn verify(&self, code: &str) -> bool {
+        self.inner.is_valid(code)
+    }
+}
+
+/// Generates and checks HOTP codes.
+#[pyclass]
+struct HOTPGenerator {
+    inner: HOTP,
+}
+
+#[pymethods]
+impl HOTPGenerator {
+    #[new]
+    #[pyo3(signature = (key_b32, counter = 0, digits = 6, algorithm = "sha1"))]
+    fn new(key_b32: &str, counter: u64, digits: usize, algorithm: &str) -> PyResult<Self> {
+        let inner = HOTPBuilder::new()
+            .base32_key(key_b32)
+            .counter(counter)
+            .output_len(digits)
+            .hash_function(parse_algorithm(algorithm)?)
+            .finalize()
+            .map_err(to_py_err)?;
+        Ok(HOTPGenerator { inner })
+    }
+
+    /// Generate the HOTP value.
+    fn generate(&self) -> String {
+        self.inner.generate()
+    }
+
+    /// Increments the internal counter.
+    fn increment_counter(&mut self) {
+        self.inner.increment_counter();
+    }
+
+    /// Checks if the given code is valid.
+    fn verify(&self, code: &str) -> bool {
+        self.inner.is_valid(code)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "libreauth")]
+fn libreauth_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<TOTPGenerator>()?;
+    m.add_class::<HOTPGenerator>()?;
+    Ok(())
+}
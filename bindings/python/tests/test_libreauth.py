@@ -0,0 +1,54 @@
+import unittest
+
+import libreauth
+
+# "12345678901234567890", the RFC 4226 and RFC 6238 key.
+KEY_B32 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+
+
+class TestTOTPGenerator(unittest.TestCase):
+    def test_generate_verify(self):
+        totp = libreauth.TOTPGenerator(KEY_B32)
+        code = totp.generate()
+        self.assertEqual(len(code), 6)
+        self.assertTrue(totp.verify(code))
+
+    def test_parameters(self):
+        totp = libreauth.TOTPGenerator(KEY_B32, period=60, digits=8, algorithm="sha256")
+        code = totp.generate()
+        self.assertEqual(len(code), 8)
+        self.assertTrue(totp.verify(code))
+
+    def test_invalid_inputs(self):
+        with self.assertRaises(ValueError):
+            libreauth.TOTPGenerator("1")
+        with self.assertRaises(ValueError):
+            libreauth.TOTPGenerator(KEY_B32, digits=4)
+        with self.assertRaises(ValueError):
+            libreauth.TOTPGenerator(KEY_B32, period=0)
+        with self.assertRaises(ValueError):
+            libreauth.TOTPGenerator(KEY_B32, algorithm="md5")
+
+
+class TestHOTPGenerator(unittest.TestCase):
+    def test_rfc4226(self):
+        hotp = libreauth.HOTPGenerator(KEY_B32)
+        for ref_code in ["755224", "287082", "359152", "969429", "338314"]:
+            self.assertEqual(hotp.generate(), ref_code)
+            self.assertTrue(hotp.verify(ref_code))
+            hotp.increment_counter()
+        self.assertFalse(hotp.verify("338314"))
+
+    def test_counter(self):
+        hotp = libreauth.HOTPGenerator(KEY_B32, counter=9)
+        self.assertEqual(hotp.generate(), "520489")
+
+    def test_invalid_inputs(self):
+        with self.assertRaises(ValueError):
+            libreauth.HOTPGenerator("1")
+        with self.assertRaises(ValueError):
+            libreauth.HOTPGenerator(KEY_B32, digits=11)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,63 @@
+import CLibreAuth
+
+/// Error returned by the C bindings, `code` being a `libreauth_oath_errno` value.
+public struct LibreAuthError: Error, Equatable {
+    public let code: UInt32
+}
+
+/// Generates and checks TOTP codes.
+public struct TOTP {
+    public let key: [UInt8]
+    public var outputLen: Int
+    public var period: UInt32
+    /// Custom value for the current Unix time, the real one being used if nil.
+    public var timestamp: Int64?
+
+    public init(key: [UInt8], outputLen: Int = 6, period: UInt32 = 30, timestamp: Int64? = nil) {
+        self.key = key
+        self.outputLen = outputLen
+        self.period = period
+        self.timestamp = timestamp
+    }
+
+    private func check(_ ret: libreauth_oath_errno) throws {
+        if ret != LIBREAUTH_OATH_SUCCESS {
+            throw LibreAuthError(code: ret.rawValue)
+        }
+    }
+
+    private func withConfig<T>(_ body: (UnsafePointer<libreauth_totp_cfg>) throws -> T) throws -> T {
+        var cfg = libreauth_totp_cfg()
+        try check(libreauth_totp_init(&cfg))
+        return try key.withUnsafeBytes { (keyPtr: UnsafeRawBufferPointer) -> T in
+            cfg.key = keyPtr.baseAddress
+            cfg.key_len = keyPtr.count
+            cfg.output_len = outputLen
+            cfg.period = period
+            if let timestamp = timestamp {
+                cfg.timestamp = timestamp
+            }
+            return try withUnsafePointer(to: &cfg) { try body($0) }
+        }
+    }
+
+    /// Generate the current TOTP value.
+    public func generate() throws -> String {
+        return try withConfig { cfg in
+            var code = [CChar](repeating: 0, count: outputLen + 1)
+            try check(libreauth_totp_generate(cfg, &code))
+            return String(cString: code)
+        }
+    }
+
+    /// Checks if the given code is valid.
+    public func isValid(_ code: String) throws -> Bool {
+        // The C binding reads `outputLen` bytes, hence a code of another length must not reach it.
+        if code.utf8.count != outputLen {
+            return false
+        }
+        return try withConfig { cfg in
+            code.withCString { libreauth_totp_is_valid(cfg, $0) == 1 }
+        }
+    }
+}
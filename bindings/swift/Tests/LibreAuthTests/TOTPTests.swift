@@ -0,0 +1,33 @@
+import XCTest
+@testable import LibreAuth
+
+final class TOTPTests: XCTestCase {
+    let key = Array("12345678901234567890".utf8)
+
+    func testGenerate() throws {
+        let totp = TOTP(key: key, timestamp: 1111111109)
+        let code = try totp.generate()
+        XCTAssertEqual(code.count, 6)
+        XCTAssertEqual(code, "081804")
+    }
+
+    func testIsValid() throws {
+        let totp = TOTP(key: key, timestamp: 1111111109)
+        XCTAssertTrue(try totp.isValid("081804"))
+        XCTAssertFalse(try totp.isValid("081805"))
+        XCTAssertFalse(try totp.isValid("08180"))
+        XCTAssertFalse(try totp.isValid(""))
+        XCTAssertFalse(try totp.isValid("0818040"))
+        XCTAssertTrue(try totp.isValid(try totp.generate()))
+    }
+
+    func testCurrentTime() throws {
+        let totp = TOTP(key: key)
+        XCTAssertTrue(try totp.isValid(try totp.generate()))
+    }
+
+    func testInvalidOutputLen() {
+        let totp = TOTP(key: key, outputLen: 5)
+        XCTAssertThrowsError(try totp.generate())
+    }
+}
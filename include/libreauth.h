@@ -37,6 +37,13 @@
 #include <stddef.h>
 #include <stdint.h>
 
+/*
+ * Version
+ */
+
+int32_t libreauth_version(char *out, size_t out_len);
+
+
 /*
  * Hash module
  */
@@ -65,6 +72,11 @@ typedef enum {
  */
 
 int32_t libreauth_keygen(void *buff, size_t buff_len);
+/*
+ * Returns 0 on success or a negated libreauth_oath_errno: -LIBREAUTH_OATH_NULL_PTR,
+ * -LIBREAUTH_OATH_INVALID_KEY_LEN or -LIBREAUTH_OATH_RANDOM_ERROR.
+ */
+int32_t libreauth_random_key(size_t len, void *out);
 
 
 /*
@@ -134,6 +146,8 @@ libreauth_pass_errno    libreauth_pass_init_from_phc(struct libreauth_pass_cfg *
 libreauth_pass_errno    libreauth_pass_hash(const struct libreauth_pass_cfg *cfg, const char *pass, char *hash, size_t hash_len);
 int32_t                 libreauth_pass_is_valid(const char *pass, const char *ref);
 int32_t                 libreauth_pass_is_valid_xhmac(const char *pass, const char *ref, const void *key, size_t key_len);
+libreauth_pass_errno    libreauth_password_hash(libreauth_pass_algo algorithm, const void *password, size_t password_len, char *out, size_t out_len);
+int32_t                 libreauth_password_verify(const char *hash, size_t hash_len, const void *password, size_t password_len);
 
 
 /*
@@ -150,11 +164,23 @@ typedef enum {
     LIBREAUTH_OATH_INVALID_KEY_LEN   = 11,
     LIBREAUTH_OATH_CODE_TOO_SMALL    = 12,
     LIBREAUTH_OATH_CODE_TOO_BIG      = 13,
+    LIBREAUTH_OATH_RANGE_TOO_LARGE   = 14,
 
     LIBREAUTH_OATH_INVALID_KEY       = 20,
     LIBREAUTH_OATH_INVALID_PERIOD    = 21,
-
-    LIBREAUTH_OATH_INVALID_UTF8      = 30
+    LIBREAUTH_OATH_INCOMPATIBLE_OPTION = 22,
+    LIBREAUTH_OATH_UNSUPPORTED_ALGORITHM = 23,
+    LIBREAUTH_OATH_WEAK_KEY          = 24,
+    LIBREAUTH_OATH_INVALID_BASE      = 25,
+
+    LIBREAUTH_OATH_INVALID_UTF8      = 30,
+    LIBREAUTH_OATH_INVALID_URI       = 31,
+    LIBREAUTH_OATH_INVALID_JSON      = 32,
+
+    LIBREAUTH_OATH_RANDOM_ERROR      = 40,
+    LIBREAUTH_OATH_CLOCK_ERROR       = 41,
+    LIBREAUTH_OATH_TIMESTAMP_OVERFLOW = 42,
+    LIBREAUTH_OATH_COUNTER_OVERFLOW  = 43
 } libreauth_oath_errno;
 
 /* HOTP */
@@ -169,9 +195,14 @@ struct libreauth_hotp_cfg {
 };
 
 libreauth_oath_errno libreauth_hotp_init(struct libreauth_hotp_cfg *cfg);
+libreauth_oath_errno libreauth_hotp_counter_set(struct libreauth_hotp_cfg *cfg, uint64_t counter);
 libreauth_oath_errno libreauth_hotp_generate(const struct libreauth_hotp_cfg *cfg, char *code);
+libreauth_oath_errno libreauth_hotp_generate_with_len(const struct libreauth_hotp_cfg *cfg, char *code, size_t code_len);
+libreauth_oath_errno libreauth_hotp_generate_n(const struct libreauth_hotp_cfg *cfg, char **codes, size_t code_len, uint32_t n);
 libreauth_oath_errno libreauth_hotp_get_uri(const struct libreauth_hotp_cfg *cfg, const char *issuer, const char *account_name, char *uri_buff, size_t uri_buff_len);
 int32_t              libreauth_hotp_is_valid(const struct libreauth_hotp_cfg *cfg, const char *code);
+int32_t              libreauth_hotp_is_valid_at_counter(const struct libreauth_hotp_cfg *cfg, const void *code, size_t code_len, uint64_t counter);
+libreauth_oath_errno libreauth_hotp_to_uri(const struct libreauth_hotp_cfg *cfg, const void *issuer, size_t issuer_len, const void *account, size_t account_len, char *out, size_t out_len);
 
 /* TOTP */
 
@@ -190,5 +221,10 @@ struct libreauth_totp_cfg {
 
 libreauth_oath_errno libreauth_totp_init(struct libreauth_totp_cfg *cfg);
 libreauth_oath_errno libreauth_totp_generate(const struct libreauth_totp_cfg *cfg, void *code);
+libreauth_oath_errno libreauth_totp_generate_with_len(const struct libreauth_totp_cfg *cfg, void *code, size_t code_len);
+libreauth_oath_errno libreauth_totp_generate_for_window(const struct libreauth_totp_cfg *cfg, uint8_t skew, char ***codes, uint32_t *codes_len);
+void                 libreauth_free_codes(char **codes, uint32_t count);
 libreauth_oath_errno libreauth_totp_get_uri(const struct libreauth_totp_cfg *cfg, const char *issuer, const char *account_name, char *uri_buff, size_t uri_buff_len);
 int32_t              libreauth_totp_is_valid(const struct libreauth_totp_cfg *cfg, const void *code);
+int32_t              libreauth_totp_is_valid_at(const struct libreauth_totp_cfg *cfg, const void *code, size_t code_len, uint64_t timestamp);
+libreauth_oath_errno libreauth_totp_to_uri(const struct libreauth_totp_cfg *cfg, const void *issuer, size_t issuer_len, const void *account, size_t account_len, char *out, size_t out_len);
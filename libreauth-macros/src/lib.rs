@@ -0,0 +1,184 @@
+//! Derive macros for [LibreAuth](https://docs.rs/libreauth/).
+//!
+//! ## OATHConfig
+//!
+//! `#[derive(OATHConfig)]` generates, for a struct with named fields, the following methods:
+//!
+//! - `to_totp_builder(&self) -> libreauth::oath::TOTPBuilder`
+//! - `to_hotp_builder(&self) -> libreauth::oath::HOTPBuilder`
+//!
+//! The fields used to configure the builders are annotated with `#[oath(...)]`:
+//!
+//! | Annotation   | Builder method  | Field type                              |
+//! |--------------|-----------------|-----------------------------------------|
+//! | `key`        | `key`           | any type implementing `AsRef<[u8]>`     |
+//! | `base32_key` | `base32_key`    | any type implementing `AsRef<str>`      |
+//! | `hash`       | `hash_function` | `libreauth::hash::HashFunction`         |
+//! | `digits`     | `output_len`    | `usize`                                 |
+//! | `period`     | `period`        | `u32`, ignored by `to_hotp_builder`     |
+//! | `counter`    | `counter`       | `u64`, ignored by `to_totp_builder`     |
+//!
+//! Exactly one field must be annotated with either `key` or `base32_key`, the other annotations
+//! are optional and the builder's default values are used for the missing ones. Fields without
+//! annotation are ignored.
+//!
+//! ```
+//! use libreauth::hash::HashFunction;
+//! use libreauth_macros::OATHConfig;
+//!
+//! #[derive(OATHConfig)]
+//! struct Token {
+//!     user_id: u32,
+//!     #[oath(base32_key)]
+//!     secret: String,
+//!     #[oath(hash)]
+//!     algorithm: HashFunction,
+//!     #[oath(digits)]
+//!     digits: usize,
+//!     #[oath(period)]
+//!     period: u32,
+//!     #[oath(counter)]
+//!     counter: u64,
+//! }
+//!
+//! let token = Token {
+//!     user_id: 42,
+//!     secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string(),
+//!     algorithm: HashFunction::Sha1,
+//!     digits: 8,
+//!     period: 30,
+//!     counter: 1,
+//! };
+//!
+//! let totp = token.to_totp_builder().timestamp(1111111090).finalize().unwrap();
+//! assert_eq!(totp.generate(), "07081804");
+//!
+//! let hotp = token.to_hotp_builder().finalize().unwrap();
+//! assert_eq!(hotp.generate(), "94287082");
+//! ```
+//!
+//! A struct without key does not compile:
+//!
+//! ```compile_fail
+//! use libreauth_macros::OATHConfig;
+//!
+//! #[derive(OATHConfig)]
+//! struct Token {
+//!     #[oath(digits)]
+//!     digits: usize,
+//! }
+//! ```
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+#[proc_macro_derive(OATHConfig, attributes(oath))]
+pub fn derive_oath_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_oath_config(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct Setters {
+    key: Option<TokenStream2>,
+    common: Vec<TokenStream2>,
+    totp: Vec<TokenStream2>,
+    hotp: Vec<TokenStream2>,
+    seen: Vec<String>,
+}
+
+fn expand_oath_config(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "OATHConfig requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "OATHConfig can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut setters = Setters::default();
+    for field in fields {
+        let name = field.ident.as_ref().unwrap();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("oath")) {
+            attr.parse_nested_meta(|meta| {
+                let param = match meta.path.get_ident() {
+                    Some(ident) => ident.to_string(),
+                    None => return Err(meta.error("unknown oath parameter")),
+                };
+                let seen_key = if param == "base32_key" { "key" } else { &param };
+                if setters.seen.iter().any(|p| p == seen_key) {
+                    return Err(meta.error(format!("duplicate oath parameter: {}", param)));
+                }
+                setters.seen.push(seen_key.to_string());
+                match param.as_str() {
+                    "key" => {
+                        setters.key = Some(quote! {
+                            .key(::std::convert::AsRef::<[u8]>::as_ref(&self.#name))
+                        })
+                    }
+                    "base32_key" => {
+                        setters.key = Some(quote! {
+                            .base32_key(::std::convert::AsRef::<str>::as_ref(&self.#name))
+                        })
+                    }
+                    "hash" => setters.common.push(quote!(.hash_function(self.#name))),
+                    "digits" => setters.common.push(quote!(.output_len(self.#name))),
+                    "period" => setters.totp.push(quote!(.period(self.#name))),
+                    "counter" => setters.hotp.push(quote!(.counter(self.#name))),
+                    _ => {
+                        return Err(meta.error(
+                            "unknown oath parameter, expected one of: key, base32_key, hash, digits, period, counter",
+                        ))
+                    }
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    let key = setters.key.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "OATHConfig requires a field annotated with #[oath(key)] or #[oath(base32_key)]",
+        )
+    })?;
+    let common = &setters.common;
+    let totp = &setters.totp;
+    let hotp = &setters.hotp;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns a TOTP builder configured using the fields annotated with `#[oath(...)]`.
+            pub fn to_totp_builder(&self) -> ::libreauth::oath::TOTPBuilder {
+                let mut builder = ::libreauth::oath::TOTPBuilder::new();
+                builder #key #(#common)* #(#totp)*;
+                builder
+            }
+
+            /// Returns an HOTP builder configured using the fields annotated with `#[oath(...)]`.
+            pub fn to_hotp_builder(&self) -> ::libreauth::oath::HOTPBuilder {
+                let mut builder = ::libreauth::oath::HOTPBuilder::new();
+                builder #key #(#common)* #(#hotp)*;
+                builder
+            }
+        }
+    })
+}
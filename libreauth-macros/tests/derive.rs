@@ -0,0 +1,95 @@
+use libreauth::hash::HashFunction;
+use libreauth_macros::OATHConfig;
+
+#[derive(OATHConfig)]
+struct RawKey {
+    #[oath(key)]
+    key: Vec<u8>,
+}
+
+#[derive(OATHConfig)]
+struct Full<'a> {
+    #[oath(key)]
+    key: &'a [u8],
+    #[oath(hash)]
+    hash: HashFunction,
+    #[oath(digits)]
+    digits: usize,
+    #[oath(period)]
+    period: u32,
+    #[oath(counter)]
+    counter: u64,
+    #[allow(dead_code)]
+    name: &'a str,
+}
+
+#[test]
+fn test_defaults() {
+    let config = RawKey {
+        key: b"12345678901234567890".to_vec(),
+    };
+    assert_eq!(
+        config.to_hotp_builder().finalize().unwrap().generate(),
+        "755224"
+    );
+    assert_eq!(
+        config
+            .to_totp_builder()
+            .output_len(8)
+            .finalize()
+            .unwrap()
+            .generate_at(59)
+            .unwrap(),
+        "94287082"
+    );
+}
+
+#[test]
+fn test_all_parameters() {
+    let key = b"12345678901234567890123456789012".to_vec();
+    let config = Full {
+        key: &key,
+        hash: HashFunction::Sha256,
+        digits: 8,
+        period: 60,
+        counter: 2,
+        name: "alice",
+    };
+    let totp = config.to_totp_builder().finalize().unwrap();
+    let reference = libreauth::oath::TOTPBuilder::new()
+        .key(&key)
+        .hash_function(HashFunction::Sha256)
+        .output_len(8)
+        .period(60)
+        .finalize()
+        .unwrap();
+    assert_eq!(
+        totp.generate_at(1111111109).unwrap(),
+        reference.generate_at(1111111109).unwrap()
+    );
+
+    let hotp = config.to_hotp_builder().finalize().unwrap();
+    let reference = libreauth::oath::HOTPBuilder::new()
+        .key(&key)
+        .hash_function(HashFunction::Sha256)
+        .output_len(8)
+        .counter(2)
+        .finalize()
+        .unwrap();
+    assert_eq!(hotp.generate(), reference.generate());
+}
+
+#[test]
+fn test_invalid_base32_key() {
+    #[derive(OATHConfig)]
+    struct Base32Key {
+        #[oath(base32_key)]
+        key: String,
+    }
+
+    let config = Base32Key {
+        key: "not base32!".to_string(),
+    };
+    assert!(config.to_totp_builder().finalize().is_err());
+    assert!(config.to_hotp_builder().finalize().is_err());
+}
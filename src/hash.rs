@@ -1,12 +1,19 @@
 //! Hash functions used in the library
 
+use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum HashFunctionError {
     ImportError,
 }
 
+/// The hash functions supported by the library.
+///
+/// New hash functions may be added in minor releases, hence matching on this enum requires a
+/// wildcard arm.
+///
 /// ## C interface
 /// The C interface uses an enum of type `libreauth_hash_function` and
 /// the members has been renamed as follows:
@@ -80,8 +87,12 @@ pub enum HashFunctionError {
 ///         </tr>
 ///     </tbody>
 /// </table>
+///
+/// The integer values of the existing members are stable and new members only ever get new
+/// values, so C code does not need to be updated when a hash function is added.
 #[repr(C)]
 #[derive(Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum HashFunction {
     Sha1 = 1,
     Sha224 = 2,
@@ -100,45 +111,85 @@ pub enum HashFunction {
     Keccak512 = 15,
 }
 
+/// Metadata about a hash function when used as the HMAC of an OATH algorithm.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OATHAlgorithmInfo {
+    /// Length of the HMAC output, in bytes.
+    pub hmac_output_len: usize,
+    /// Recommended key length, in bytes, which is the length of the HMAC output as advised by
+    /// RFC 4226.
+    pub recommended_key_len: usize,
+    /// Name of the hash function, as displayed.
+    pub digest_name: &'static str,
+}
+
+impl HashFunction {
+    /// Returns the metadata of the hash function.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::hash::HashFunction;
+    ///
+    /// let info = HashFunction::Sha256.info();
+    /// assert_eq!(info.hmac_output_len, 32);
+    /// assert_eq!(info.recommended_key_len, 32);
+    /// assert_eq!(info.digest_name, "SHA256");
+    /// ```
+    pub fn info(self) -> OATHAlgorithmInfo {
+        let (hmac_output_len, digest_name) = match self {
+            HashFunction::Sha1 => (20, "SHA1"),
+            HashFunction::Sha224 => (28, "SHA224"),
+            HashFunction::Sha256 => (32, "SHA256"),
+            HashFunction::Sha384 => (48, "SHA384"),
+            HashFunction::Sha512 => (64, "SHA512"),
+            HashFunction::Sha512Trunc224 => (28, "SHA512-224"),
+            HashFunction::Sha512Trunc256 => (32, "SHA512-256"),
+            HashFunction::Sha3_224 => (28, "SHA3-224"),
+            HashFunction::Sha3_256 => (32, "SHA3-256"),
+            HashFunction::Sha3_384 => (48, "SHA3-384"),
+            HashFunction::Sha3_512 => (64, "SHA3-512"),
+            HashFunction::Keccak224 => (28, "Keccak224"),
+            HashFunction::Keccak256 => (32, "Keccak256"),
+            HashFunction::Keccak384 => (48, "Keccak384"),
+            HashFunction::Keccak512 => (64, "Keccak512"),
+        };
+        OATHAlgorithmInfo {
+            hmac_output_len,
+            recommended_key_len: hmac_output_len,
+            digest_name,
+        }
+    }
+}
+
 impl fmt::Display for HashFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            HashFunction::Sha1 => "SHA1",
-            HashFunction::Sha224 => "SHA224",
-            HashFunction::Sha256 => "SHA256",
-            HashFunction::Sha384 => "SHA384",
-            HashFunction::Sha512 => "SHA512",
-            HashFunction::Sha512Trunc224 => "SHA512-224",
-            HashFunction::Sha512Trunc256 => "SHA512-256",
-            HashFunction::Sha3_224 => "SHA3-224",
-            HashFunction::Sha3_256 => "SHA3-256",
-            HashFunction::Sha3_384 => "SHA3-384",
-            HashFunction::Sha3_512 => "SHA3-512",
-            HashFunction::Keccak224 => "Keccak224",
-            HashFunction::Keccak256 => "Keccak256",
-            HashFunction::Keccak384 => "Keccak384",
-            HashFunction::Keccak512 => "Keccak512",
-        };
-        write!(f, "{}", s)
+        write!(f, "{}", self.info().digest_name)
     }
 }
 
 impl FromStr for HashFunction {
     type Err = HashFunctionError;
 
+    /// Parses a hash function name. The comparison is case insensitive and both hyphens and
+    /// underscores are ignored, hence `SHA-256`, `sha256` and `Sha_256` are equivalent.
     fn from_str(data: &str) -> Result<Self, Self::Err> {
-        Ok(match data.to_lowercase().as_str() {
+        let name: String = data
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_lowercase();
+        Ok(match name.as_str() {
             "sha1" => HashFunction::Sha1,
             "sha224" => HashFunction::Sha224,
             "sha256" => HashFunction::Sha256,
             "sha384" => HashFunction::Sha384,
             "sha512" => HashFunction::Sha512,
-            "sha512-224" | "sha512t224" => HashFunction::Sha512Trunc224,
-            "sha512-256" | "sha512t256" => HashFunction::Sha512Trunc256,
-            "sha3-224" => HashFunction::Sha3_224,
-            "sha3-256" => HashFunction::Sha3_256,
-            "sha3-384" => HashFunction::Sha3_384,
-            "sha3-512" => HashFunction::Sha3_512,
+            "sha512224" | "sha512t224" => HashFunction::Sha512Trunc224,
+            "sha512256" | "sha512t256" => HashFunction::Sha512Trunc256,
+            "sha3224" => HashFunction::Sha3_224,
+            "sha3256" => HashFunction::Sha3_256,
+            "sha3384" => HashFunction::Sha3_384,
+            "sha3512" => HashFunction::Sha3_512,
             "keccak224" => HashFunction::Keccak224,
             "keccak256" => HashFunction::Keccak256,
             "keccak384" => HashFunction::Keccak384,
@@ -149,3 +200,156 @@ impl FromStr for HashFunction {
         })
     }
 }
+
+/// Same as [`FromStr`], the error being convertible into the `UnsupportedAlgorithm` error code of
+/// the oath module.
+///
+/// ## Examples
+/// ```
+/// use libreauth::hash::HashFunction;
+/// use std::convert::TryFrom;
+///
+/// assert!(HashFunction::try_from("SHA-256").unwrap() == HashFunction::Sha256);
+/// assert!(HashFunction::try_from("md5").is_err());
+/// ```
+impl TryFrom<&str> for HashFunction {
+    type Error = HashFunctionError;
+
+    fn try_from(data: &str) -> Result<Self, Self::Error> {
+        data.parse()
+    }
+}
+
+/// Converts an integer into a hash function using the same values as the C interface, e.g. `1` for
+/// `Sha1` and `3` for `Sha256`. Since those values are stable, they can safely be stored.
+///
+/// ## Examples
+/// ```
+/// use libreauth::hash::HashFunction;
+/// use std::convert::TryFrom;
+///
+/// let stored = u32::from(HashFunction::Sha256);
+/// assert!(HashFunction::try_from(stored).unwrap() == HashFunction::Sha256);
+/// assert!(HashFunction::try_from(0).is_err());
+/// ```
+impl TryFrom<u32> for HashFunction {
+    type Error = HashFunctionError;
+
+    fn try_from(data: u32) -> Result<Self, Self::Error> {
+        Ok(match data {
+            1 => HashFunction::Sha1,
+            2 => HashFunction::Sha224,
+            3 => HashFunction::Sha256,
+            4 => HashFunction::Sha384,
+            5 => HashFunction::Sha512,
+            6 => HashFunction::Sha512Trunc224,
+            7 => HashFunction::Sha512Trunc256,
+            8 => HashFunction::Sha3_224,
+            9 => HashFunction::Sha3_256,
+            10 => HashFunction::Sha3_384,
+            11 => HashFunction::Sha3_512,
+            12 => HashFunction::Keccak224,
+            13 => HashFunction::Keccak256,
+            14 => HashFunction::Keccak384,
+            15 => HashFunction::Keccak512,
+            _ => {
+                return Err(HashFunctionError::ImportError);
+            }
+        })
+    }
+}
+
+impl From<HashFunction> for u32 {
+    fn from(hash_function: HashFunction) -> Self {
+        hash_function as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashFunction, HashFunctionError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_try_from_str() {
+        let tests = [
+            ("sha1", HashFunction::Sha1),
+            ("SHA1", HashFunction::Sha1),
+            ("sha-1", HashFunction::Sha1),
+            ("Sha_1", HashFunction::Sha1),
+            ("SHA-256", HashFunction::Sha256),
+            ("sha512", HashFunction::Sha512),
+            ("SHA512-224", HashFunction::Sha512Trunc224),
+            ("sha512t256", HashFunction::Sha512Trunc256),
+            ("SHA3-256", HashFunction::Sha3_256),
+            ("sha3_512", HashFunction::Sha3_512),
+            ("Keccak384", HashFunction::Keccak384),
+        ];
+        for (name, hash_function) in tests.iter() {
+            assert!(HashFunction::try_from(*name).unwrap() == *hash_function);
+        }
+    }
+
+    #[test]
+    fn test_try_from_str_display() {
+        let hash_functions = [
+            HashFunction::Sha1,
+            HashFunction::Sha224,
+            HashFunction::Sha256,
+            HashFunction::Sha384,
+            HashFunction::Sha512,
+            HashFunction::Sha512Trunc224,
+            HashFunction::Sha512Trunc256,
+            HashFunction::Sha3_224,
+            HashFunction::Sha3_256,
+            HashFunction::Sha3_384,
+            HashFunction::Sha3_512,
+            HashFunction::Keccak224,
+            HashFunction::Keccak256,
+            HashFunction::Keccak384,
+            HashFunction::Keccak512,
+        ];
+        for hash_function in hash_functions.iter() {
+            let name = hash_function.to_string();
+            assert!(HashFunction::try_from(name.as_str()).unwrap() == *hash_function);
+        }
+    }
+
+    #[test]
+    fn test_try_from_u32() {
+        assert!(HashFunction::try_from(1).unwrap() == HashFunction::Sha1);
+        assert!(HashFunction::try_from(3).unwrap() == HashFunction::Sha256);
+        assert!(HashFunction::try_from(5).unwrap() == HashFunction::Sha512);
+        for nb in 1..=15 {
+            let hash_function = HashFunction::try_from(nb).unwrap();
+            assert_eq!(u32::from(hash_function), nb);
+        }
+        for nb in [0, 16, 42, u32::max_value()].iter() {
+            assert_eq!(
+                HashFunction::try_from(*nb).err(),
+                Some(HashFunctionError::ImportError)
+            );
+        }
+    }
+
+    #[test]
+    fn test_info() {
+        let info = HashFunction::Sha1.info();
+        assert_eq!(info.hmac_output_len, 20);
+        assert_eq!(info.recommended_key_len, 20);
+        assert_eq!(info.digest_name, "SHA1");
+        assert_eq!(HashFunction::Sha512Trunc256.info().hmac_output_len, 32);
+        assert_eq!(HashFunction::Sha3_384.info().hmac_output_len, 48);
+        assert_eq!(HashFunction::Keccak512.info().digest_name, "Keccak512");
+    }
+
+    #[test]
+    fn test_try_from_str_invalid() {
+        for name in ["", "md5", "sha", "sha3", "sha-3-1", "keccak"].iter() {
+            match HashFunction::try_from(*name) {
+                Err(HashFunctionError::ImportError) => {}
+                _ => panic!("{} has been parsed", name),
+            }
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use super::KeyBuilder;
 use crate::get_slice_mut;
+#[cfg(feature = "oath")]
+use crate::oath::ErrorCode;
+#[cfg(feature = "oath")]
+use getrandom::getrandom;
 use libc;
 use std;
 
+#[cfg(feature = "oath")]
+const RANDOM_KEY_MIN_LEN: usize = 16;
+
 /// [C binding] Generate a random key.
 ///
 /// # Parameters
@@ -38,3 +45,45 @@ pub unsafe extern "C" fn libreauth_keygen(buff: *mut u8, buff_len: libc::size_t)
     key[..len].clone_from_slice(&out[..len]);
     0
 }
+
+/// [C binding] Fill a buffer with random bytes suitable for use as an OATH key.
+///
+/// # Parameters
+///
+/// - `len`: length of the buffer, in bytes, which must be at least 16
+/// - `out`: pointer to the buffer that will be filled with the random key
+///
+/// # Return code
+///
+/// - 0 in case of success
+/// - `-LIBREAUTH_OATH_NULL_PTR` if `out` is a null pointer
+/// - `-LIBREAUTH_OATH_INVALID_KEY_LEN` if `len` is lower than 16
+/// - `-LIBREAUTH_OATH_RANDOM_ERROR` if the operating system's random number generator failed
+///
+/// ## Examples
+/// ```c
+/// uint8_t key[32] = {0};
+/// int32_t ret = libreauth_random_key(sizeof(key), key);
+/// if (ret != 0) {
+///     // Handle the error.
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[cfg(feature = "oath")]
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_random_key(len: libc::size_t, out: *mut u8) -> i32 {
+    if out.is_null() {
+        return -(ErrorCode::NullPtr as i32);
+    }
+    if len < RANDOM_KEY_MIN_LEN {
+        return -(ErrorCode::InvalidKeyLen as i32);
+    }
+    let key = get_slice_mut!(out, len);
+    match getrandom(key) {
+        Ok(_) => 0,
+        Err(_) => -(ErrorCode::RandomError as i32),
+    }
+}
@@ -37,6 +37,8 @@ mod cbindings;
 
 #[cfg(feature = "cbindings")]
 pub use self::cbindings::libreauth_keygen;
+#[cfg(all(feature = "cbindings", feature = "oath"))]
+pub use self::cbindings::libreauth_random_key;
 use base32;
 use base64;
 use getrandom::getrandom;
@@ -17,3 +17,11 @@ pub mod key;
 pub mod oath;
 #[cfg(feature = "pass")]
 pub mod pass;
+#[cfg(all(feature = "cbindings", feature = "oath"))]
+mod version;
+#[cfg(all(feature = "cbindings", feature = "oath"))]
+pub use version::libreauth_version;
+
+// Checks the Rust examples of the README.
+#[cfg(all(doctest, feature = "key", feature = "oath"))]
+doc_comment::doctest!("../README.md");
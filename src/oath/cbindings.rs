@@ -1,13 +1,14 @@
 use super::HOTPBuilder;
 use super::TOTPBuilder;
-use crate::oath::{ErrorCode, HashFunction};
+use crate::oath::{ErrorCode, HashFunction, KeyMaterial};
 use crate::{
     deref_ptr, deref_ptr_mut, get_slice, get_slice_mut, get_string, get_value_or_errno,
     get_value_or_false,
 };
 use libc;
 use std;
-use std::ffi::CStr;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
 use std::time::SystemTime;
 
 macro_rules! otp_init {
@@ -77,20 +78,30 @@ pub struct TOTPcfg {
     hash_function: HashFunction,
 }
 
-fn write_code(code: &[u8], dest: &mut [u8]) {
+// Copies the code and its null terminator into the buffer. If the buffer is too small to hold
+// both, it is left untouched and `NotEnoughSpace` is returned.
+fn write_code(code: &[u8], dest: &mut [u8]) -> Result<(), ErrorCode> {
     let len = code.len();
+    if len >= dest.len() {
+        return Err(ErrorCode::NotEnoughSpace);
+    }
     dest[..len].clone_from_slice(&code[..len]);
     dest[len] = 0;
+    Ok(())
 }
 
 fn get_cfg<T>(cfg: *const T) -> Result<&'static T, ErrorCode> {
-    if cfg.is_null() {
-        return Err(ErrorCode::NullPtr);
-    }
     let cfg: &T = unsafe { deref_ptr!(cfg, Err(ErrorCode::NullPtr)) };
     Ok(cfg)
 }
 
+// The C caller owns the configuration and must not access it while the returned reference is
+// alive, which holds for the duration of a C binding call.
+fn get_cfg_mut<T>(cfg: *mut T) -> Result<&'static mut T, ErrorCode> {
+    let cfg: &mut T = unsafe { deref_ptr_mut!(cfg, Err(ErrorCode::NullPtr)) };
+    Ok(cfg)
+}
+
 fn get_code(code: *const u8, code_len: usize) -> Result<String, ErrorCode> {
     if code.is_null() {
         return Err(ErrorCode::NullPtr);
@@ -106,7 +117,7 @@ fn get_mut_code(code: *mut u8, code_len: usize) -> Result<&'static mut [u8], Err
     if code.is_null() {
         return Err(ErrorCode::NullPtr);
     }
-    Ok(unsafe { get_slice_mut!(code, code_len + 1) })
+    Ok(unsafe { get_slice_mut!(code, code_len) })
 }
 
 fn get_output_base(output_base: *const libc::c_char) -> Result<String, ErrorCode> {
@@ -120,13 +131,20 @@ fn get_output_base(output_base: *const libc::c_char) -> Result<String, ErrorCode
     }
 }
 
-fn get_key(key: *const u8, key_len: usize) -> Result<Vec<u8>, ErrorCode> {
+// The key is returned as a `KeyMaterial` so the copy made from the C buffer is zeroed once the
+// C binding returns.
+fn get_key(key: *const u8, key_len: usize) -> Result<KeyMaterial, ErrorCode> {
+    get_key_optional(key, key_len)?.ok_or(ErrorCode::NullPtr)
+}
+
+// Same as `get_key` for optional parameters: a null pointer means the parameter is not set.
+fn get_key_optional(key: *const u8, key_len: usize) -> Result<Option<KeyMaterial>, ErrorCode> {
     if key.is_null() {
-        Err(ErrorCode::NullPtr)
+        Ok(None)
     } else {
         match key_len {
             0 => Err(ErrorCode::InvalidKeyLen),
-            l => Ok(unsafe { get_slice!(key, l) }),
+            l => Ok(Some(KeyMaterial::new(unsafe { get_slice!(key, l) }))),
         }
     }
 }
@@ -163,6 +181,37 @@ pub unsafe extern "C" fn libreauth_hotp_init(cfg: *mut HOTPcfg) -> ErrorCode {
     }
 }
 
+/// [C binding] Set the counter of a `struct libreauth_hotp_cfg`.
+///
+/// This is meant to store the new counter once a code has been successfully checked, the other
+/// fields of the configuration are left untouched.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_hotp_cfg`
+/// - `counter`: the new counter value
+///
+/// # Examples
+///
+/// ```c
+/// if (libreauth_hotp_is_valid(&cfg, code)) {
+///     uint32_t ret = libreauth_hotp_counter_set(&cfg, cfg.counter + 1);
+///     if (ret != LIBREAUTH_OATH_SUCCESS) {
+///         // Handle the error.
+///     }
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_hotp_counter_set(cfg: *mut HOTPcfg, counter: u64) -> ErrorCode {
+    let cfg = get_value_or_errno!(get_cfg_mut(cfg));
+    cfg.counter = counter;
+    ErrorCode::Success
+}
+
 /// [C binding] Generate an HOTP code according to the given configuration and stores it in the supplied buffer.
 ///
 /// # Parameters
@@ -193,8 +242,40 @@ pub unsafe extern "C" fn libreauth_hotp_init(cfg: *mut HOTPcfg) -> ErrorCode {
 /// ```
 #[no_mangle]
 pub extern "C" fn libreauth_hotp_generate(cfg: *const HOTPcfg, code: *mut u8) -> ErrorCode {
+    let output_len = get_value_or_errno!(get_cfg(cfg)).output_len;
+    libreauth_hotp_generate_with_len(cfg, code, output_len.saturating_add(1))
+}
+
+/// [C binding] Same as `libreauth_hotp_generate`, but the size of the supplied buffer is given
+/// in `code_len`. If it cannot hold the code and its null terminator, `NotEnoughSpace` is
+/// returned and the buffer is left untouched.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_hotp_cfg`
+/// - `code`: buffer that will hold the string representing the code
+/// - `code_len`: size of the buffer, in bytes
+///
+/// # Examples
+///
+/// ```c
+/// char code[DEFAULT_BUFF_LEN + 1];
+///
+/// uint32_t ret = libreauth_hotp_generate_with_len(&cfg, code, sizeof(code));
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+///
+/// printf("HOTP code: %s\n", code);
+/// ```
+#[no_mangle]
+pub extern "C" fn libreauth_hotp_generate_with_len(
+    cfg: *const HOTPcfg,
+    code: *mut u8,
+    code_len: libc::size_t,
+) -> ErrorCode {
     let cfg = get_value_or_errno!(get_cfg(cfg));
-    let code = get_value_or_errno!(get_mut_code(code, cfg.output_len as usize));
+    let code = get_value_or_errno!(get_mut_code(code, code_len));
     let output_base = get_value_or_errno!(get_output_base(cfg.output_base));
     let key = get_value_or_errno!(get_key(cfg.key, cfg.key_len as usize));
     match HOTPBuilder::new()
@@ -207,7 +288,86 @@ pub extern "C" fn libreauth_hotp_generate(cfg: *const HOTPcfg, code: *mut u8) ->
     {
         Ok(hotp) => {
             let ref_code = hotp.generate().into_bytes();
-            write_code(&ref_code, code);
+            get_value_or_errno!(write_code(&ref_code, code));
+            ErrorCode::Success
+        }
+        Err(errno) => errno,
+    }
+}
+
+/// [C binding] Generate `n` successive HOTP codes, starting from the configured counter, and
+/// stores them in the supplied buffers.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_hotp_cfg`
+/// - `codes`: array of `n` buffers that will hold the strings representing the codes
+/// - `code_len`: size of each buffer, in bytes (must be greater than `cfg.output_len`)
+/// - `n`: number of codes to generate
+///
+/// # Examples
+///
+/// ```c
+/// struct libreauth_hotp_cfg cfg;
+/// const char key[] = "12345678901234567890";
+/// char buff[3][DEFAULT_BUFF_LEN + 1];
+/// char *codes[3] = {buff[0], buff[1], buff[2]};
+///
+/// uint32_t ret = libreauth_hotp_init(&cfg);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// cfg.key = key;
+/// cfg.key_len = strlen(key);
+///
+/// ret = libreauth_hotp_generate_n(&cfg, codes, DEFAULT_BUFF_LEN + 1, 3);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+///
+/// for (int i = 0; i < 3; ++i) {
+///     printf("HOTP code %d: %s\n", i, codes[i]);
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_hotp_generate_n(
+    cfg: *const HOTPcfg,
+    codes: *mut *mut u8,
+    code_len: libc::size_t,
+    n: u32,
+) -> ErrorCode {
+    let cfg = get_value_or_errno!(get_cfg(cfg));
+    if codes.is_null() {
+        return ErrorCode::NullPtr;
+    }
+    if code_len <= cfg.output_len {
+        return ErrorCode::NotEnoughSpace;
+    }
+    let codes = get_slice!(codes, n as usize);
+    if codes.iter().any(|c| c.is_null()) {
+        return ErrorCode::NullPtr;
+    }
+    let output_base = get_value_or_errno!(get_output_base(cfg.output_base));
+    let key = get_value_or_errno!(get_key(cfg.key, cfg.key_len));
+    match HOTPBuilder::new()
+        .key(&key)
+        .output_len(cfg.output_len)
+        .output_base(&output_base)
+        .hash_function(cfg.hash_function)
+        .counter(cfg.counter)
+        .finalize()
+    {
+        Ok(mut hotp) => {
+            for dest in codes {
+                let code = get_value_or_errno!(get_mut_code(dest, code_len));
+                let ref_code = hotp.generate().into_bytes();
+                get_value_or_errno!(write_code(&ref_code, code));
+                hotp.increment_counter();
+            }
             ErrorCode::Success
         }
         Err(errno) => errno,
@@ -265,6 +425,76 @@ pub extern "C" fn libreauth_hotp_is_valid(cfg: *const HOTPcfg, code: *const u8)
     }
 }
 
+fn hotp_is_valid_at_counter(
+    cfg: *const HOTPcfg,
+    code: *const u8,
+    code_len: usize,
+    counter: u64,
+) -> Result<bool, ErrorCode> {
+    let cfg = get_cfg(cfg)?;
+    let code = get_code(code, code_len)?;
+    let output_base = get_output_base(cfg.output_base)?;
+    let key = get_key(cfg.key, cfg.key_len as usize)?;
+    let hotp = HOTPBuilder::new()
+        .key(&key)
+        .output_len(cfg.output_len as usize)
+        .output_base(&output_base)
+        .hash_function(cfg.hash_function)
+        .finalize()?;
+    Ok(hotp.is_valid_at(&code, counter))
+}
+
+/// [C binding] Check whether or not the supplied HOTP code is valid for the given counter. The
+/// configuration's `counter` is ignored and left untouched.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_hotp_cfg`
+/// - `code`: buffer holding the code to check
+/// - `code_len`: length of the code, in bytes
+/// - `counter`: counter for which the code is checked
+///
+/// # Return value
+///
+/// `1` if the code is valid, `0` if it is not and, if an error occurred, the opposite of the
+/// error's value, e.g. `-LIBREAUTH_OATH_NULL_PTR`.
+///
+/// # Examples
+///
+/// ```c
+/// struct libreauth_hotp_cfg cfg;
+/// const char key[] = "12345678901234567890";
+///
+/// uint32_t ret = libreauth_hotp_init(&cfg);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// cfg.key = key;
+/// cfg.key_len = strlen(key);
+///
+/// int32_t valid = libreauth_hotp_is_valid_at_counter(&cfg, "287082", 6, 1);
+/// if (valid < 0) {
+///     // Handle the error.
+/// } else if (valid) {
+///     printf("Valid HOTP code\n");
+/// } else {
+///     printf("Invalid HOTP code\n");
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn libreauth_hotp_is_valid_at_counter(
+    cfg: *const HOTPcfg,
+    code: *const u8,
+    code_len: libc::size_t,
+    counter: u64,
+) -> i32 {
+    match hotp_is_valid_at_counter(cfg, code, code_len, counter) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(errno) => -(errno as i32),
+    }
+}
+
 /// [C binding] Generate the key URI.
 ///
 /// # Parameters
@@ -318,6 +548,189 @@ pub unsafe extern "C" fn libreauth_hotp_get_uri(
     }
 }
 
+/// [C binding] Generate the key URI, the issuer and account name being given with their length
+/// instead of being null-terminated. The URI includes the configuration's counter.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_hotp_cfg`
+/// - `issuer`: buffer holding the issuer's name
+/// - `issuer_len`: length of the issuer's name, in bytes
+/// - `account`: buffer holding the name of the user account
+/// - `account_len`: length of the name of the user account, in bytes
+/// - `out`: buffer that will hold the null-terminated string representing the URI
+/// - `out_len`: buffer's size, in bytes
+///
+/// # Examples
+///
+/// ```c
+/// struct libreauth_hotp_cfg cfg;
+/// const char key[] = "12345678901234567890";
+/// const char issuer[] = "Provider1";
+/// const char account[] = "alice@example.com";
+/// char uri[256] = {0};
+///
+/// uint32_t ret = libreauth_hotp_init(&cfg);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// cfg.key = key;
+/// cfg.key_len = strlen(key);
+///
+/// ret = libreauth_hotp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), uri, sizeof(uri));
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+///
+/// printf("Key URI: %s\n", uri);
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[cfg(feature = "oath-uri")]
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_hotp_to_uri(
+    cfg: *const HOTPcfg,
+    issuer: *const u8,
+    issuer_len: libc::size_t,
+    account: *const u8,
+    account_len: libc::size_t,
+    out: *mut u8,
+    out_len: libc::size_t,
+) -> ErrorCode {
+    let cfg = get_value_or_errno!(get_cfg(cfg));
+    let issuer = get_value_or_errno!(get_code(issuer, issuer_len));
+    let account = get_value_or_errno!(get_code(account, account_len));
+    if out.is_null() {
+        return ErrorCode::NullPtr;
+    }
+    let out = get_slice_mut!(out, out_len);
+    let output_base = get_value_or_errno!(get_output_base(cfg.output_base));
+    let key = get_value_or_errno!(get_key(cfg.key, cfg.key_len as usize));
+    match HOTPBuilder::new()
+        .key(&key)
+        .output_len(cfg.output_len as usize)
+        .output_base(&output_base)
+        .hash_function(cfg.hash_function)
+        .counter(cfg.counter)
+        .finalize()
+    {
+        Ok(hotp) => {
+            let uri = hotp.key_uri_format(&issuer, &account).finalize();
+            get_value_or_errno!(write_code(uri.as_bytes(), out));
+            ErrorCode::Success
+        }
+        Err(errno) => errno,
+    }
+}
+
+/// [C binding] Generate the TOTP codes of the `skew` periods before the current one, the current
+/// one and the `skew` periods after it, from the oldest to the newest.
+///
+/// The codes are null-terminated strings stored in an array allocated by this function. The
+/// array is written in `codes` and its number of elements in `codes_len`. It must be released
+/// using `libreauth_free_codes`.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_totp_cfg`
+/// - `skew`: number of periods before and after the current one
+/// - `codes`: pointer that will hold the array of codes
+/// - `codes_len`: pointer that will hold the number of codes
+///
+/// # Examples
+///
+/// ```c
+/// struct libreauth_totp_cfg cfg;
+/// const char key[] = "12345678901234567890";
+/// char **codes = NULL;
+/// uint32_t codes_len = 0;
+///
+/// uint32_t ret = libreauth_totp_init(&cfg);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// cfg.key = key;
+/// cfg.key_len = strlen(key);
+///
+/// ret = libreauth_totp_generate_for_window(&cfg, 1, &codes, &codes_len);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+///
+/// for (uint32_t i = 0; i < codes_len; ++i) {
+///     printf("TOTP code %u: %s\n", i, codes[i]);
+/// }
+/// libreauth_free_codes(codes, codes_len);
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_totp_generate_for_window(
+    cfg: *const TOTPcfg,
+    skew: u8,
+    codes: *mut *mut *mut u8,
+    codes_len: *mut u32,
+) -> ErrorCode {
+    let cfg = get_value_or_errno!(get_cfg(cfg));
+    if codes.is_null() || codes_len.is_null() {
+        return ErrorCode::NullPtr;
+    }
+    let output_base = get_value_or_errno!(get_output_base(cfg.output_base));
+    let key = get_value_or_errno!(get_key(cfg.key, cfg.key_len as usize));
+    let totp = get_value_or_errno!(TOTPBuilder::new()
+        .key(&key)
+        .output_len(cfg.output_len as usize)
+        .output_base(&output_base)
+        .hash_function(cfg.hash_function)
+        .timestamp(cfg.timestamp)
+        .period(cfg.period)
+        .initial_time(cfg.initial_time)
+        .finalize());
+    let window = get_value_or_errno!(totp.window_codes(skew));
+    let mut ptrs = Vec::with_capacity(window.len());
+    for code in window {
+        match CString::new(code) {
+            Ok(code) => ptrs.push(code.into_raw() as *mut u8),
+            Err(_) => {
+                let len = ptrs.len() as u32;
+                libreauth_free_codes(Box::into_raw(ptrs.into_boxed_slice()) as *mut *mut u8, len);
+                return ErrorCode::InvalidUTF8;
+            }
+        }
+    }
+    *codes_len = ptrs.len() as u32;
+    *codes = Box::into_raw(ptrs.into_boxed_slice()) as *mut *mut u8;
+    ErrorCode::Success
+}
+
+/// [C binding] Release an array of codes allocated by `libreauth_totp_generate_for_window`.
+///
+/// # Parameters
+///
+/// - `codes`: array of codes, may be null
+/// - `count`: number of codes in the array, as returned by the allocating function
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_free_codes(codes: *mut *mut u8, count: u32) {
+    if codes.is_null() {
+        return;
+    }
+    // The array has been allocated as a boxed slice, hence its capacity is its length.
+    let codes = Vec::from_raw_parts(codes, count as usize, count as usize);
+    for code in codes.iter() {
+        if !code.is_null() {
+            drop(CString::from_raw(*code as *mut libc::c_char));
+        }
+    }
+}
+
 /// [C binding] Initialize a `struct libreauth_totp_cfg` with the default values.
 ///
 /// # Parameter
@@ -343,14 +756,15 @@ pub unsafe extern "C" fn libreauth_hotp_get_uri(
 /// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
 #[no_mangle]
 pub unsafe extern "C" fn libreauth_totp_init(cfg: *mut TOTPcfg) -> ErrorCode {
+    let timestamp = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(_) => return ErrorCode::ClockError,
+    };
     let res: Result<&mut TOTPcfg, ErrorCode> = otp_init!(
         TOTPcfg,
         cfg,
         timestamp,
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64,
+        timestamp,
         positive_tolerance,
         0,
         negative_tolerance,
@@ -396,8 +810,40 @@ pub unsafe extern "C" fn libreauth_totp_init(cfg: *mut TOTPcfg) -> ErrorCode {
 /// ```
 #[no_mangle]
 pub extern "C" fn libreauth_totp_generate(cfg: *const TOTPcfg, code: *mut u8) -> ErrorCode {
+    let output_len = get_value_or_errno!(get_cfg(cfg)).output_len;
+    libreauth_totp_generate_with_len(cfg, code, output_len.saturating_add(1))
+}
+
+/// [C binding] Same as `libreauth_totp_generate`, but the size of the supplied buffer is given
+/// in `code_len`. If it cannot hold the code and its null terminator, `NotEnoughSpace` is
+/// returned and the buffer is left untouched.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_totp_cfg`
+/// - `code`: buffer that will hold the string representing the code
+/// - `code_len`: size of the buffer, in bytes
+///
+/// # Examples
+///
+/// ```c
+/// char code[DEFAULT_BUFF_LEN + 1];
+///
+/// uint32_t ret = libreauth_totp_generate_with_len(&cfg, code, sizeof(code));
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+///
+/// printf("TOTP code: %s\n", code);
+/// ```
+#[no_mangle]
+pub extern "C" fn libreauth_totp_generate_with_len(
+    cfg: *const TOTPcfg,
+    code: *mut u8,
+    code_len: libc::size_t,
+) -> ErrorCode {
     let cfg = get_value_or_errno!(get_cfg(cfg));
-    let code = get_value_or_errno!(get_mut_code(code, cfg.output_len as usize));
+    let code = get_value_or_errno!(get_mut_code(code, code_len));
     let output_base = get_value_or_errno!(get_output_base(cfg.output_base));
     let key = get_value_or_errno!(get_key(cfg.key, cfg.key_len as usize));
     match TOTPBuilder::new()
@@ -412,7 +858,7 @@ pub extern "C" fn libreauth_totp_generate(cfg: *const TOTPcfg, code: *mut u8) ->
     {
         Ok(hotp) => {
             let ref_code = hotp.generate().into_bytes();
-            write_code(&ref_code, code);
+            get_value_or_errno!(write_code(&ref_code, code));
             ErrorCode::Success
         }
         Err(errno) => errno,
@@ -474,6 +920,84 @@ pub extern "C" fn libreauth_totp_is_valid(cfg: *const TOTPcfg, code: *const u8)
     }
 }
 
+fn totp_is_valid_at(
+    cfg: *const TOTPcfg,
+    code: *const u8,
+    code_len: usize,
+    timestamp: u64,
+) -> Result<bool, ErrorCode> {
+    let cfg = get_cfg(cfg)?;
+    let code = get_code(code, code_len)?;
+    let output_base = get_output_base(cfg.output_base)?;
+    let key = get_key(cfg.key, cfg.key_len as usize)?;
+    if timestamp < cfg.initial_time {
+        return Err(ErrorCode::TimestampOverflow);
+    }
+    let timestamp = i64::try_from(timestamp).map_err(|_| ErrorCode::TimestampOverflow)?;
+    let totp = TOTPBuilder::new()
+        .key(&key)
+        .output_len(cfg.output_len as usize)
+        .output_base(&output_base)
+        .hash_function(cfg.hash_function)
+        .period(cfg.period)
+        .initial_time(cfg.initial_time)
+        .positive_tolerance(cfg.positive_tolerance)
+        .negative_tolerance(cfg.negative_tolerance)
+        .finalize()?;
+    Ok(totp.is_valid_at(&code, timestamp))
+}
+
+/// [C binding] Check whether or not the supplied TOTP code is valid at the given Unix timestamp.
+/// The configuration's `timestamp` is ignored.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_totp_cfg`
+/// - `code`: buffer holding the code to check
+/// - `code_len`: length of the code, in bytes
+/// - `timestamp`: Unix timestamp, in seconds, at which the code is checked
+///
+/// # Return value
+///
+/// `1` if the code is valid, `0` if it is not and, if an error occurred, the opposite of the
+/// error's value, e.g. `-LIBREAUTH_OATH_NULL_PTR`.
+///
+/// # Examples
+///
+/// ```c
+/// struct libreauth_totp_cfg cfg;
+/// const char key[] = "12345678901234567890";
+///
+/// uint32_t ret = libreauth_totp_init(&cfg);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// cfg.key = key;
+/// cfg.key_len = strlen(key);
+///
+/// int32_t valid = libreauth_totp_is_valid_at(&cfg, "081804", 6, 1111111109);
+/// if (valid < 0) {
+///     // Handle the error.
+/// } else if (valid) {
+///     printf("Valid TOTP code\n");
+/// } else {
+///     printf("Invalid TOTP code\n");
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn libreauth_totp_is_valid_at(
+    cfg: *const TOTPcfg,
+    code: *const u8,
+    code_len: libc::size_t,
+    timestamp: u64,
+) -> i32 {
+    match totp_is_valid_at(cfg, code, code_len, timestamp) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(errno) => -(errno as i32),
+    }
+}
+
 /// [C binding] Generate the key URI.
 ///
 /// # Parameters
@@ -530,3 +1054,215 @@ pub unsafe extern "C" fn libreauth_totp_get_uri(
         Err(errno) => errno,
     }
 }
+
+/// [C binding] Generate the key URI, the issuer and account name being given with their length
+/// instead of being null-terminated.
+///
+/// # Parameters
+///
+/// - `cfg`: pointer to a `struct libreauth_totp_cfg`
+/// - `issuer`: buffer holding the issuer's name
+/// - `issuer_len`: length of the issuer's name, in bytes
+/// - `account`: buffer holding the name of the user account
+/// - `account_len`: length of the name of the user account, in bytes
+/// - `out`: buffer that will hold the null-terminated string representing the URI
+/// - `out_len`: buffer's size, in bytes
+///
+/// # Examples
+///
+/// ```c
+/// struct libreauth_totp_cfg cfg;
+/// const char key[] = "12345678901234567890";
+/// const char issuer[] = "Provider1";
+/// const char account[] = "alice@example.com";
+/// char uri[256] = {0};
+///
+/// uint32_t ret = libreauth_totp_init(&cfg);
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// cfg.key = key;
+/// cfg.key_len = strlen(key);
+///
+/// ret = libreauth_totp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), uri, sizeof(uri));
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+///
+/// printf("Key URI: %s\n", uri);
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[cfg(feature = "oath-uri")]
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_totp_to_uri(
+    cfg: *const TOTPcfg,
+    issuer: *const u8,
+    issuer_len: libc::size_t,
+    account: *const u8,
+    account_len: libc::size_t,
+    out: *mut u8,
+    out_len: libc::size_t,
+) -> ErrorCode {
+    let cfg = get_value_or_errno!(get_cfg(cfg));
+    let issuer = get_value_or_errno!(get_code(issuer, issuer_len));
+    let account = get_value_or_errno!(get_code(account, account_len));
+    if out.is_null() {
+        return ErrorCode::NullPtr;
+    }
+    let out = get_slice_mut!(out, out_len);
+    let output_base = get_value_or_errno!(get_output_base(cfg.output_base));
+    let key = get_value_or_errno!(get_key(cfg.key, cfg.key_len as usize));
+    match TOTPBuilder::new()
+        .key(&key)
+        .output_len(cfg.output_len as usize)
+        .output_base(&output_base)
+        .hash_function(cfg.hash_function)
+        .timestamp(cfg.timestamp)
+        .period(cfg.period)
+        .initial_time(cfg.initial_time)
+        .positive_tolerance(cfg.positive_tolerance)
+        .negative_tolerance(cfg.negative_tolerance)
+        .finalize()
+    {
+        Ok(totp) => {
+            let uri = totp.key_uri_format(&issuer, &account).finalize();
+            get_value_or_errno!(write_code(uri.as_bytes(), out));
+            ErrorCode::Success
+        }
+        Err(errno) => errno,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        get_cfg, get_cfg_mut, get_code, get_key, get_key_optional, get_mut_code, get_output_base,
+        write_code, HOTPcfg,
+    };
+    use crate::oath::ErrorCode;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_write_code() {
+        let mut dest = [0xffu8; 8];
+        assert_eq!(write_code(b"123456", &mut dest), Ok(()));
+        assert_eq!(&dest, b"123456\0\xff");
+
+        let mut dest = [0xffu8; 7];
+        assert_eq!(write_code(b"123456", &mut dest), Ok(()));
+        assert_eq!(&dest, b"123456\0");
+
+        let mut dest = [0xffu8; 1];
+        assert_eq!(write_code(b"", &mut dest), Ok(()));
+        assert_eq!(&dest, b"\0");
+
+        let mut dest = [0xffu8; 6];
+        assert_eq!(
+            write_code(b"123456", &mut dest),
+            Err(ErrorCode::NotEnoughSpace)
+        );
+        assert_eq!(&dest, b"\xff\xff\xff\xff\xff\xff");
+        let mut dest = [0xffu8; 0];
+        assert_eq!(write_code(b"", &mut dest), Err(ErrorCode::NotEnoughSpace));
+    }
+
+    #[test]
+    fn test_get_cfg() {
+        let cfg = std::ptr::null::<HOTPcfg>();
+        assert_eq!(get_cfg(cfg).err(), Some(ErrorCode::NullPtr));
+
+        let value = 42u64;
+        assert_eq!(get_cfg(&value as *const u64), Ok(&42));
+    }
+
+    #[test]
+    fn test_get_cfg_mut() {
+        let cfg = std::ptr::null_mut::<HOTPcfg>();
+        assert_eq!(get_cfg_mut(cfg).err(), Some(ErrorCode::NullPtr));
+
+        let mut value = 42u64;
+        let cfg = get_cfg_mut(&mut value as *mut u64).unwrap();
+        *cfg += 1;
+        assert_eq!(value, 43);
+    }
+
+    #[test]
+    fn test_get_code() {
+        assert_eq!(
+            get_code(std::ptr::null(), 6).err(),
+            Some(ErrorCode::NullPtr)
+        );
+
+        let code = b"123456";
+        assert_eq!(get_code(code.as_ptr(), 0), Ok(String::new()));
+        assert_eq!(get_code(code.as_ptr(), 3), Ok("123".to_string()));
+        assert_eq!(get_code(code.as_ptr(), 6), Ok("123456".to_string()));
+
+        let code = [0x31u8, 0xc3, 0x28, 0x32];
+        assert_eq!(
+            get_code(code.as_ptr(), code.len()).err(),
+            Some(ErrorCode::InvalidUTF8)
+        );
+        assert_eq!(get_code(code.as_ptr(), 1), Ok("1".to_string()));
+    }
+
+    #[test]
+    fn test_get_mut_code() {
+        assert_eq!(
+            get_mut_code(std::ptr::null_mut(), 6).err(),
+            Some(ErrorCode::NullPtr)
+        );
+
+        let mut code = [0u8; 7];
+        assert_eq!(get_mut_code(code.as_mut_ptr(), 0).unwrap().len(), 0);
+        assert_eq!(get_mut_code(code.as_mut_ptr(), 7).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_get_output_base() {
+        assert_eq!(
+            get_output_base(std::ptr::null()),
+            Ok(crate::oath::DEFAULT_OTP_OUT_BASE.to_string())
+        );
+        for base in ["", "0"].iter() {
+            let base = CString::new(*base).unwrap();
+            assert_eq!(
+                get_output_base(base.as_ptr()).err(),
+                Some(ErrorCode::InvalidBaseLen)
+            );
+        }
+        let base = CString::new("01").unwrap();
+        assert_eq!(get_output_base(base.as_ptr()), Ok("01".to_string()));
+    }
+
+    #[test]
+    fn test_get_key() {
+        assert_eq!(get_key(std::ptr::null(), 4).err(), Some(ErrorCode::NullPtr));
+        assert_eq!(get_key(std::ptr::null(), 0).err(), Some(ErrorCode::NullPtr));
+
+        let key = b"12345678901234567890";
+        assert_eq!(
+            get_key(key.as_ptr(), 0).err(),
+            Some(ErrorCode::InvalidKeyLen)
+        );
+        assert_eq!(get_key(key.as_ptr(), 1).unwrap().as_slice(), b"1");
+        assert_eq!(get_key(key.as_ptr(), key.len()).unwrap().as_slice(), key);
+    }
+
+    #[test]
+    fn test_get_key_optional() {
+        assert!(get_key_optional(std::ptr::null(), 4).unwrap().is_none());
+        assert!(get_key_optional(std::ptr::null(), 0).unwrap().is_none());
+
+        let key = b"12345678901234567890";
+        assert_eq!(
+            get_key_optional(key.as_ptr(), 0).err(),
+            Some(ErrorCode::InvalidKeyLen)
+        );
+        let res = get_key_optional(key.as_ptr(), key.len()).unwrap();
+        assert_eq!(res.unwrap().as_slice(), key);
+    }
+}
@@ -1,17 +1,20 @@
 #[cfg(feature = "oath-uri")]
 use super::DEFAULT_KEY_URI_PARAM_POLICY;
-use super::{ErrorCode, HashFunction, DEFAULT_OTP_HASH, DEFAULT_OTP_OUT_BASE, DEFAULT_OTP_OUT_LEN};
+use super::{
+    key_gen, Encoding, ErrorCode, HashFunction, HexKey, TOTPBuilder, DEFAULT_OTP_HASH,
+    DEFAULT_OTP_OUT_BASE, DEFAULT_OTP_OUT_LEN, KEY_MIN_LEN, MAX_COUNTER_RANGE, MAX_SEQUENCE_LEN,
+};
 #[cfg(feature = "oath-uri")]
 use crate::oath::key_uri::{KeyUriBuilder, UriType};
-use base32;
 use base64;
-use hex;
 use hmac::{Hmac, Mac};
 use sha1::Sha1;
 use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512Trunc224, Sha512Trunc256};
 use sha3::{Keccak224, Keccak256, Keccak384, Keccak512, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
 #[cfg(feature = "oath-uri")]
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeLess};
 
 macro_rules! compute_hmac {
     ($obj: ident, $hash: ty, $input: ident) => {{
@@ -21,6 +24,23 @@ macro_rules! compute_hmac {
     }};
 }
 
+/// The intermediate values of an HOTP code computation, as returned by
+/// [`HOTPBuilder::debug_info`](struct.HOTPBuilder.html#method.debug_info).
+#[cfg(feature = "debug")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HOTPDebugInfo {
+    /// The counter.
+    pub counter: u64,
+    /// The hex encoded HMAC of the counter.
+    pub hmac: String,
+    /// The offset of the four bytes extracted by the dynamic truncation.
+    pub offset: usize,
+    /// The 31 bits integer extracted by the dynamic truncation, before the modulo is applied.
+    pub truncated_value: u32,
+    /// The resulting code.
+    pub code: String,
+}
+
 /// Generates, manipulates and checks HOTP codes.
 pub struct HOTP {
     key: Vec<u8>,
@@ -31,14 +51,39 @@ pub struct HOTP {
 }
 
 impl HOTP {
-    fn reduce_result(&self, hs: &[u8]) -> u32 {
+    fn hmac(&self, msg: &[u8]) -> Vec<u8> {
+        match self.hash_function {
+            HashFunction::Sha1 => compute_hmac!(self, Sha1, msg),
+            HashFunction::Sha224 => compute_hmac!(self, Sha224, msg),
+            HashFunction::Sha256 => compute_hmac!(self, Sha256, msg),
+            HashFunction::Sha384 => compute_hmac!(self, Sha384, msg),
+            HashFunction::Sha512 => compute_hmac!(self, Sha512, msg),
+            HashFunction::Sha512Trunc224 => compute_hmac!(self, Sha512Trunc224, msg),
+            HashFunction::Sha512Trunc256 => compute_hmac!(self, Sha512Trunc256, msg),
+            HashFunction::Sha3_224 => compute_hmac!(self, Sha3_224, msg),
+            HashFunction::Sha3_256 => compute_hmac!(self, Sha3_256, msg),
+            HashFunction::Sha3_384 => compute_hmac!(self, Sha3_384, msg),
+            HashFunction::Sha3_512 => compute_hmac!(self, Sha3_512, msg),
+            HashFunction::Keccak224 => compute_hmac!(self, Keccak224, msg),
+            HashFunction::Keccak256 => compute_hmac!(self, Keccak256, msg),
+            HashFunction::Keccak384 => compute_hmac!(self, Keccak384, msg),
+            HashFunction::Keccak512 => compute_hmac!(self, Keccak512, msg),
+        }
+    }
+
+    // Returns the offset used by the dynamic truncation and the resulting 31 bits integer.
+    fn truncate(hs: &[u8]) -> (usize, u32) {
         let offset = (hs[hs.len() - 1] & 0xf) as usize;
         let hash = hs[offset..offset + 4].to_vec();
         let snum: u32 = ((u32::from(hash[0]) & 0x7f) << 24)
             | ((u32::from(hash[1]) & 0xff) << 16)
             | ((u32::from(hash[2]) & 0xff) << 8)
             | (u32::from(hash[3]) & 0xff);
+        (offset, snum)
+    }
 
+    fn reduce_result(&self, hs: &[u8]) -> u32 {
+        let (_, snum) = HOTP::truncate(hs);
         let base = self.output_base.len() as u32;
         snum % base.pow(self.output_len as u32)
     }
@@ -80,38 +125,69 @@ impl HOTP {
     /// assert_eq!(code, "287082");
     /// ```
     pub fn generate(&self) -> String {
-        let msg = [
-            ((self.counter >> 56) & 0xff) as u8,
-            ((self.counter >> 48) & 0xff) as u8,
-            ((self.counter >> 40) & 0xff) as u8,
-            ((self.counter >> 32) & 0xff) as u8,
-            ((self.counter >> 24) & 0xff) as u8,
-            ((self.counter >> 16) & 0xff) as u8,
-            ((self.counter >> 8) & 0xff) as u8,
-            (self.counter & 0xff) as u8,
-        ];
-        let result: Vec<u8> = match self.hash_function {
-            HashFunction::Sha1 => compute_hmac!(self, Sha1, msg),
-            HashFunction::Sha224 => compute_hmac!(self, Sha224, msg),
-            HashFunction::Sha256 => compute_hmac!(self, Sha256, msg),
-            HashFunction::Sha384 => compute_hmac!(self, Sha384, msg),
-            HashFunction::Sha512 => compute_hmac!(self, Sha512, msg),
-            HashFunction::Sha512Trunc224 => compute_hmac!(self, Sha512Trunc224, msg),
-            HashFunction::Sha512Trunc256 => compute_hmac!(self, Sha512Trunc256, msg),
-            HashFunction::Sha3_224 => compute_hmac!(self, Sha3_224, msg),
-            HashFunction::Sha3_256 => compute_hmac!(self, Sha3_256, msg),
-            HashFunction::Sha3_384 => compute_hmac!(self, Sha3_384, msg),
-            HashFunction::Sha3_512 => compute_hmac!(self, Sha3_512, msg),
-            HashFunction::Keccak224 => compute_hmac!(self, Keccak224, msg),
-            HashFunction::Keccak256 => compute_hmac!(self, Keccak256, msg),
-            HashFunction::Keccak384 => compute_hmac!(self, Keccak384, msg),
-            HashFunction::Keccak512 => compute_hmac!(self, Keccak512, msg),
-        };
+        log_debug!("HOTP code generated for counter {}", self.counter);
+        self.generate_at(self.counter)
+    }
+
+    /// Generate the HOTP value for the given counter instead of the internal one.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let hotp = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(hotp.generate_at(0), "755224");
+    /// assert_eq!(hotp.generate_at(1), "287082");
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(hash_function = %self.hash_function, output_len = self.output_len)
+        )
+    )]
+    pub fn generate_at(&self, counter: u64) -> String {
+        count_event!("libreauth.hotp.generate_count");
+        self.compute_at(counter)
+    }
+
+    // Same as `generate_at`, but not counted as a generation. This is used in order to generate
+    // the reference codes during a verification, including by TOTP.
+    pub(crate) fn compute_at(&self, counter: u64) -> String {
+        let result = self.counter_hmac(counter);
         let hs = result.as_slice();
         let nb = self.reduce_result(&hs);
         self.format_result(nb)
     }
 
+    fn counter_hmac(&self, counter: u64) -> Vec<u8> {
+        let msg = [
+            ((counter >> 56) & 0xff) as u8,
+            ((counter >> 48) & 0xff) as u8,
+            ((counter >> 40) & 0xff) as u8,
+            ((counter >> 32) & 0xff) as u8,
+            ((counter >> 24) & 0xff) as u8,
+            ((counter >> 16) & 0xff) as u8,
+            ((counter >> 8) & 0xff) as u8,
+            (counter & 0xff) as u8,
+        ];
+        self.hmac(&msg)
+    }
+
+    // Returns the hex encoded HMAC, the truncation offset, the truncated integer and the code
+    // computed for the given counter.
+    #[cfg(feature = "debug")]
+    pub(crate) fn debug_values(&self, counter: u64) -> (String, usize, u32, String) {
+        let result = self.counter_hmac(counter);
+        let (offset, truncated_value) = HOTP::truncate(&result);
+        let code = self.format_result(self.reduce_result(&result));
+        (hex::encode(&result), offset, truncated_value, code)
+    }
+
     /// Increments the internal counter.
     pub fn increment_counter(&mut self) -> &mut HOTP {
         self.counter += 1;
@@ -132,75 +208,110 @@ impl HOTP {
     /// assert!(valid);
     /// ```
     pub fn is_valid(&self, code: &str) -> bool {
+        let is_valid = self.is_valid_at(code, self.counter);
+        log_debug!(
+            "HOTP verification {} for counter {}",
+            if is_valid { "succeeded" } else { "failed" },
+            self.counter
+        );
+        is_valid
+    }
+
+    /// Checks if the given code is valid for the given counter instead of the internal one, using
+    /// the same double HMAC verification as [is_valid](#method.is_valid).
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let hotp = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(hotp.is_valid_at("287082", 1));
+    /// assert!(!hotp.is_valid_at("287082", 0));
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, code),
+            fields(hash_function = %self.hash_function, output_len = self.output_len)
+        )
+    )]
+    pub fn is_valid_at(&self, code: &str, counter: u64) -> bool {
+        let is_valid = self.check_at(code, counter);
+        count_verification!("hotp", is_valid);
+        is_valid
+    }
+
+    /// Same as [is_valid_at](#method.is_valid_at).
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let hotp = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(hotp.verify("287082", 1));
+    /// assert!(!hotp.verify("287082", 0));
+    /// ```
+    pub fn verify(&self, code: &str, counter: u64) -> bool {
+        self.is_valid_at(code, counter)
+    }
+
+    // Same as `is_valid_at`, but not counted as a verification.
+    pub(crate) fn check_at(&self, code: &str, counter: u64) -> bool {
         if code.len() != self.output_len {
             return false;
         }
-        let r1 = self.generate();
+        let r1 = self.compute_at(counter);
         let ref_code = r1.as_str().as_bytes();
         let code = code.as_bytes();
-        let (code, ref_code) = match self.hash_function {
-            HashFunction::Sha1 => (
-                compute_hmac!(self, Sha1, code),
-                compute_hmac!(self, Sha1, ref_code),
-            ),
-            HashFunction::Sha224 => (
-                compute_hmac!(self, Sha224, code),
-                compute_hmac!(self, Sha224, ref_code),
-            ),
-            HashFunction::Sha256 => (
-                compute_hmac!(self, Sha256, code),
-                compute_hmac!(self, Sha256, ref_code),
-            ),
-            HashFunction::Sha384 => (
-                compute_hmac!(self, Sha384, code),
-                compute_hmac!(self, Sha384, ref_code),
-            ),
-            HashFunction::Sha512 => (
-                compute_hmac!(self, Sha512, code),
-                compute_hmac!(self, Sha512, ref_code),
-            ),
-            HashFunction::Sha512Trunc224 => (
-                compute_hmac!(self, Sha512Trunc224, code),
-                compute_hmac!(self, Sha512Trunc224, ref_code),
-            ),
-            HashFunction::Sha512Trunc256 => (
-                compute_hmac!(self, Sha512Trunc256, code),
-                compute_hmac!(self, Sha512Trunc256, ref_code),
-            ),
-            HashFunction::Sha3_224 => (
-                compute_hmac!(self, Sha3_224, code),
-                compute_hmac!(self, Sha3_224, ref_code),
-            ),
-            HashFunction::Sha3_256 => (
-                compute_hmac!(self, Sha3_256, code),
-                compute_hmac!(self, Sha3_256, ref_code),
-            ),
-            HashFunction::Sha3_384 => (
-                compute_hmac!(self, Sha3_384, code),
-                compute_hmac!(self, Sha3_384, ref_code),
-            ),
-            HashFunction::Sha3_512 => (
-                compute_hmac!(self, Sha3_512, code),
-                compute_hmac!(self, Sha3_512, ref_code),
-            ),
-            HashFunction::Keccak224 => (
-                compute_hmac!(self, Keccak224, code),
-                compute_hmac!(self, Keccak224, ref_code),
-            ),
-            HashFunction::Keccak256 => (
-                compute_hmac!(self, Keccak256, code),
-                compute_hmac!(self, Keccak256, ref_code),
-            ),
-            HashFunction::Keccak384 => (
-                compute_hmac!(self, Keccak384, code),
-                compute_hmac!(self, Keccak384, ref_code),
-            ),
-            HashFunction::Keccak512 => (
-                compute_hmac!(self, Keccak512, code),
-                compute_hmac!(self, Keccak512, ref_code),
-            ),
-        };
-        code == ref_code
+        self.hmac(code) == self.hmac(ref_code)
+    }
+
+    /// Checks a list of codes and returns the index of the first valid one, if any.
+    ///
+    /// Every code of the list is checked using the same double HMAC verification as
+    /// [is_valid](#method.is_valid) and the whole list is always processed, so the time taken does
+    /// not reveal the position of the valid code.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let hotp = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(hotp.verify_list(&["123456", "755224", "287082"]), Some(1));
+    /// assert_eq!(hotp.verify_list(&["123456", "287082"]), None);
+    /// ```
+    pub fn verify_list(&self, codes: &[&str]) -> Option<usize> {
+        let (found, index) = self.find_code(codes);
+        count_verification!("hotp", bool::from(found));
+        if bool::from(found) {
+            Some(index as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether or not one of the codes is valid and, if so, the index of the first valid
+    /// one. The whole list is processed in constant time.
+    pub(crate) fn find_code(&self, codes: &[&str]) -> (Choice, u64) {
+        let ref_code = self.hmac(self.compute_at(self.counter).as_bytes());
+        let mut found = Choice::from(0);
+        let mut index = 0u64;
+        for (i, code) in codes.iter().enumerate() {
+            let is_match = self.hmac(code.as_bytes()).ct_eq(&ref_code) & !found;
+            index.conditional_assign(&(i as u64), is_match);
+            found |= is_match;
+        }
+        (found, index)
     }
 
     /// Creates the Key Uri Format according to the [Google authenticator
@@ -303,12 +414,38 @@ impl HOTP {
 ///     .finalize();
 /// ```
 pub struct HOTPBuilder {
-    key: Option<Vec<u8>>,
+    pub(crate) key: Option<Vec<u8>>,
     counter: u64,
-    output_len: usize,
-    output_base: String,
-    hash_function: HashFunction,
-    runtime_error: Option<ErrorCode>,
+    pub(crate) output_len: usize,
+    pub(crate) output_base: String,
+    pub(crate) hash_function: HashFunction,
+    pub(crate) runtime_error: Option<ErrorCode>,
+    minimum_key_len: Option<usize>,
+}
+
+/// The parameters of an HOTP builder, meant to be stored and later turned back into a builder.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::HOTPBuilder;
+///
+/// let key_ascii = "12345678901234567890".to_owned();
+/// let config = HOTPBuilder::new()
+///     .ascii_key(&key_ascii)
+///     .counter(1)
+///     .to_config()
+///     .unwrap();
+///
+/// let mut hotp = HOTPBuilder::from_config(&config).unwrap().finalize().unwrap();
+/// assert_eq!(hotp.generate(), "287082");
+/// ```
+#[derive(Clone)]
+pub struct HOTPConfig {
+    pub key: Vec<u8>,
+    pub counter: u64,
+    pub output_len: usize,
+    pub output_base: String,
+    pub hash_function: HashFunction,
 }
 
 impl Default for HOTPBuilder {
@@ -317,6 +454,39 @@ impl Default for HOTPBuilder {
     }
 }
 
+/// Creates a builder with the default configuration, the given key and hash function. If the key
+/// is shorter than 16 bytes, `ErrorCode::InvalidKeyLen` is returned.
+///
+/// ## Examples
+/// ```
+/// use libreauth::hash::HashFunction;
+/// use libreauth::oath::{ErrorCode, HOTPBuilder};
+/// use std::convert::TryFrom;
+///
+/// let key = b"12345678901234567890123456789012".to_vec();
+/// let hotp = HOTPBuilder::try_from((key, HashFunction::Sha256))
+///     .unwrap()
+///     .finalize()
+///     .unwrap();
+/// assert_eq!(hotp.generate(), "920136");
+///
+/// let builder = HOTPBuilder::try_from((b"1234567890".to_vec(), HashFunction::Sha1));
+/// assert_eq!(builder.err(), Some(ErrorCode::InvalidKeyLen));
+/// ```
+impl TryFrom<(Vec<u8>, HashFunction)> for HOTPBuilder {
+    type Error = ErrorCode;
+
+    fn try_from((key, hash_function): (Vec<u8>, HashFunction)) -> Result<Self, Self::Error> {
+        if key.len() < KEY_MIN_LEN {
+            return Err(ErrorCode::InvalidKeyLen);
+        }
+        let mut builder = HOTPBuilder::new();
+        builder.key = Some(key);
+        builder.hash_function = hash_function;
+        Ok(builder)
+    }
+}
+
 impl HOTPBuilder {
     /// Generates the base configuration for HOTP code generation.
     pub fn new() -> HOTPBuilder {
@@ -327,28 +497,215 @@ impl HOTPBuilder {
             output_base: DEFAULT_OTP_OUT_BASE.to_string(),
             hash_function: DEFAULT_OTP_HASH,
             runtime_error: None,
+            minimum_key_len: None,
+        }
+    }
+
+    /// Generates the base configuration for HOTP code generation with a random key of `len`
+    /// bytes, as generated by [key_gen](fn.key_gen.html). The key can be retrieved using
+    /// [to_config](#method.to_config) in order to be stored or shared with the client.
+    ///
+    /// If the key cannot be generated, the error (e.g. `ErrorCode::InvalidKeyLen` if `len` is
+    /// lower than 16) is returned when finalizing.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::oath::HOTPBuilder;
+    ///
+    /// let mut builder = HOTPBuilder::with_key_len(20);
+    /// let key = builder.to_config().unwrap().key;
+    /// assert_eq!(key.len(), 20);
+    ///
+    /// let hotp = builder.output_len(8).finalize().unwrap();
+    /// assert_eq!(hotp.generate().len(), 8);
+    /// ```
+    pub fn with_key_len(len: usize) -> HOTPBuilder {
+        let mut builder = HOTPBuilder::new();
+        match key_gen(len) {
+            Ok(key) => builder.key = Some(key),
+            Err(e) => builder.runtime_error = Some(e),
         }
+        builder
     }
 
     builder_common!(HOTPBuilder);
 
+    fn key_min_len(&self) -> usize {
+        self.minimum_key_len.unwrap_or(KEY_MIN_LEN)
+    }
+
     /// Sets the counter. Default is 0.
     pub fn counter(&mut self, counter: u64) -> &mut HOTPBuilder {
         self.counter = counter;
         self
     }
 
+    /// Sets the minimum length of the key, in bytes. Once set, `finalize` returns
+    /// `ErrorCode::InvalidKeyLen` if the key is shorter, so the resulting HOTP never generates nor
+    /// verifies codes using such a key. It also replaces the 16 bytes minimum used by
+    /// `validate_key`. By default, `finalize` does not check the key length.
+    ///
+    /// Setting a minimum lower than 16 bytes is insecure and should only be done for testing,
+    /// for example with short test vectors.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::oath::{ErrorCode, HOTPBuilder};
+    ///
+    /// let hotp = HOTPBuilder::new()
+    ///     .ascii_key("12345678901234567890")
+    ///     .minimum_key_len(32)
+    ///     .finalize();
+    /// assert_eq!(hotp.err(), Some(ErrorCode::InvalidKeyLen));
+    /// ```
+    pub fn minimum_key_len(&mut self, len: usize) -> &mut HOTPBuilder {
+        self.minimum_key_len = Some(len);
+        self
+    }
+
+    /// Returns a short description of the algorithm, made of the hash function and the code
+    /// length. It does not contain any secret, so it can be logged.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::hash::HashFunction;
+    ///
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// assert_eq!(builder.algorithm_suite(), "HOTP-SHA1-6");
+    /// builder.hash_function(HashFunction::Sha3_256).output_len(8);
+    /// assert_eq!(builder.algorithm_suite(), "HOTP-SHA3-256-8");
+    /// ```
+    pub fn algorithm_suite(&self) -> String {
+        format!("HOTP-{}-{}", self.hash_function, self.output_len)
+    }
+
+    /// Returns the intermediate values used to compute the code of the configured counter, as
+    /// listed in the appendix D of RFC 4226. This is useful in order to find out why another
+    /// implementation generates different codes. If the configuration is invalid, the same error
+    /// as `finalize` is returned.
+    ///
+    /// **WARNING**: The HMAC allows to compute the code, hence those values should not be
+    /// exposed to anybody but the developers.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let debug_info = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .counter(1)
+    ///     .debug_info()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(debug_info.hmac, "75a48a19d4cbe100644e8ac1397eea747a2d33ab");
+    /// assert_eq!(debug_info.truncated_value, 1094287082);
+    /// assert_eq!(debug_info.code, "287082");
+    /// ```
+    #[cfg(feature = "debug")]
+    pub fn debug_info(&self) -> Result<HOTPDebugInfo, ErrorCode> {
+        let (hmac, offset, truncated_value, code) = self.finalize()?.debug_values(self.counter);
+        Ok(HOTPDebugInfo {
+            counter: self.counter,
+            hmac,
+            offset,
+            truncated_value,
+            code,
+        })
+    }
+
+    /// Returns a copy of the shared secret that can be formatted as hexadecimal, or `None` if no
+    /// secret has been set. Unlike a `Vec<u8>`, this copy is zeroed in memory when dropped.
+    ///
+    /// **WARNING**: The shared secret allows to generate codes, hence it should only be
+    /// displayed to its owner, for example in order to configure another device.
+    ///
+    /// ## Examples
+    /// ```
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// builder.base32_key("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    ///
+    /// let key = builder.key_as_hex_display().unwrap();
+    /// assert_eq!(format!("{:X}", key), "3132333435363738393031323334353637383930");
+    /// ```
+    pub fn key_as_hex_display(&self) -> Option<HexKey> {
+        self.key.as_ref().map(|key| HexKey::new(key.clone()))
+    }
+
+    /// Returns the 31 bits integer resulting from the dynamic truncation of the HMAC of the
+    /// configured counter, as defined in the section 5.3 of RFC 4226, before it is reduced to
+    /// the code's length. If the configuration is invalid, for example if no key has been set,
+    /// the same error as `finalize` is returned.
+    ///
+    /// **WARNING**: This value allows to compute the code, hence it should not be exposed to
+    /// anybody but the developers.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let value = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .counter(1)
+    ///     .truncate_to_u32()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(value, 1094287082);
+    /// ```
+    #[cfg(feature = "debug")]
+    pub fn truncate_to_u32(&self) -> Result<u32, ErrorCode> {
+        let hotp = self.finalize()?;
+        let (_, truncated_value) = HOTP::truncate(&hotp.counter_hmac(self.counter));
+        Ok(truncated_value)
+    }
+
+    /// Creates a builder from a stored configuration. Every parameter is checked the same way
+    /// `finalize` does, so the returned builder can be finalized without error.
+    pub fn from_config(config: &HOTPConfig) -> Result<HOTPBuilder, ErrorCode> {
+        let mut builder = HOTPBuilder::new();
+        builder
+            .key(&config.key)
+            .counter(config.counter)
+            .output_len(config.output_len)
+            .output_base(&config.output_base)
+            .hash_function(config.hash_function);
+        builder.finalize()?;
+        Ok(builder)
+    }
+
+    /// Returns the configuration of this builder so it can be stored. The same errors as
+    /// `finalize` are returned if the configuration is invalid.
+    pub fn to_config(&self) -> Result<HOTPConfig, ErrorCode> {
+        let hotp = self.finalize()?;
+        Ok(HOTPConfig {
+            key: hotp.key,
+            counter: hotp.counter,
+            output_len: hotp.output_len,
+            output_base: hotp.output_base,
+            hash_function: hotp.hash_function,
+        })
+    }
+
     /// Returns the finalized HOTP object.
     pub fn finalize(&self) -> Result<HOTP, ErrorCode> {
+        let hotp = self.build();
+        if hotp.is_err() {
+            count_event!("libreauth.config_error_count");
+        }
+        hotp
+    }
+
+    fn build(&self) -> Result<HOTP, ErrorCode> {
         if let Some(e) = self.runtime_error {
             return Err(e);
         }
+        self.check_output_base()?;
         match self.code_length() {
             n if n < 1_000_000 => return Err(ErrorCode::CodeTooSmall),
             n if n > 2_147_483_648 => return Err(ErrorCode::CodeTooBig),
             _ => (),
         }
         match self.key {
+            Some(ref k) if k.len() < self.minimum_key_len.unwrap_or(0) => {
+                Err(ErrorCode::InvalidKeyLen)
+            }
             Some(ref k) => Ok(HOTP {
                 key: k.clone(),
                 counter: self.counter,
@@ -359,48 +716,397 @@ impl HOTPBuilder {
             None => Err(ErrorCode::InvalidKey),
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::HOTPBuilder;
-    use crate::hash::HashFunction;
-    #[cfg(feature = "oath-uri")]
-    use crate::oath::ParametersVisibility;
-
-    #[test]
-    fn test_hotp_key_simple() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
-
-        let hotp = HOTPBuilder::new().key(&key).finalize().unwrap();
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 0);
-        assert_eq!(hotp.output_len, 6);
-        match hotp.hash_function {
-            HashFunction::Sha1 => assert!(true),
-            _ => assert!(false),
+    /// Generates `count` consecutive codes, starting at the counter `start`. The internal counter
+    /// is ignored.
+    ///
+    /// At most 10000 codes can be generated at once, otherwise `ErrorCode::RangeTooLarge` is
+    /// returned. If the last counter would exceed `u64::MAX`, `ErrorCode::CounterOverflow` is
+    /// returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let codes = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .generate_sequence(1, 3)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(codes, vec!["287082", "359152", "969429"]);
+    /// ```
+    pub fn generate_sequence(&self, start: u64, count: usize) -> Result<Vec<String>, ErrorCode> {
+        if count > MAX_SEQUENCE_LEN {
+            return Err(ErrorCode::RangeTooLarge);
         }
-
-        let code = hotp.generate();
-        assert_eq!(code.len(), 6);
-        assert_eq!(code, "755224");
+        if count > 0 && start.checked_add(count as u64 - 1).is_none() {
+            return Err(ErrorCode::CounterOverflow);
+        }
+        let hotp = self.finalize()?;
+        Ok((0..count as u64)
+            .map(|i| hotp.generate_at(start + i))
+            .collect())
     }
 
-    #[test]
-    fn test_hotp_key_full() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
+    /// Checks if the given code is valid for the configured counter only, without any look-ahead.
+    /// If the configuration is invalid, the same error as `finalize` is returned.
+    ///
+    /// Unlike `window_verify` or `counter_distance`, a code generated for any other counter is
+    /// rejected. This is appropriate when the client's counter is known to be synchronized with
+    /// the server's one, for example with hardware tokens that only increment their counter when
+    /// the code is submitted: accepting codes ahead of the counter would only widen the window
+    /// an attacker can guess in.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// builder.ascii_key(&key_ascii).counter(1);
+    ///
+    /// assert!(builder.verify_strict("287082").unwrap());
+    /// assert!(!builder.verify_strict("359152").unwrap());
+    /// ```
+    pub fn verify_strict(&self, code: &str) -> Result<bool, ErrorCode> {
+        Ok(self.finalize()?.is_valid(code))
+    }
 
-        let hotp = HOTPBuilder::new()
-            .key(&key)
-            .counter(5)
-            .output_len(8)
-            .hash_function(HashFunction::Sha256)
-            .finalize()
+    /// Checks if the given code is valid for the configured counter, taking the same time
+    /// whatever the code is. If the configuration is invalid, the same error as `finalize` is
+    /// returned.
+    ///
+    /// [HOTP::is_valid](struct.HOTP.html#method.is_valid) immediately rejects a code whose
+    /// length differs from the configured one, which reveals through timing whether the length is
+    /// right. This function always generates the reference code and computes both HMAC of the
+    /// double HMAC verification, then compares their results and the lengths in constant time.
+    /// Rejecting a malformed code hence costs as much as checking a well formed one: a code
+    /// generation and two HMAC, instead of nothing.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// builder.ascii_key(&key_ascii).counter(1);
+    ///
+    /// assert!(builder.verify_constant_time("287082").unwrap());
+    /// assert!(!builder.verify_constant_time("359152").unwrap());
+    /// assert!(!builder.verify_constant_time("28708").unwrap());
+    /// ```
+    pub fn verify_constant_time(&self, code: &str) -> Result<bool, ErrorCode> {
+        let hotp = self.finalize()?;
+        let ref_code = hotp.compute_at(self.counter);
+        let same_len = (code.len() as u64).ct_eq(&(ref_code.len() as u64));
+        let same_hmac = hotp
+            .hmac(code.as_bytes())
+            .ct_eq(&hotp.hmac(ref_code.as_bytes()));
+        let is_valid = bool::from(same_len & same_hmac);
+        count_verification!("hotp", is_valid);
+        Ok(is_valid)
+    }
+
+    /// Same as [verify_strict](#method.verify_strict) except that a code whose length differs
+    /// from the configured one is reported as an error instead of being rejected:
+    /// `ErrorCode::CodeTooSmall` if it is too short and `ErrorCode::CodeTooBig` if it is too
+    /// long. This allows to tell the user the code has been mistyped.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::oath::{ErrorCode, HOTPBuilder};
+    ///
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut builder = HOTPBuilder::new();
+    /// builder.ascii_key(&key_ascii).counter(1);
+    ///
+    /// assert!(builder.verify_code_len("287082").unwrap());
+    /// assert!(!builder.verify_code_len("359152").unwrap());
+    /// assert_eq!(builder.verify_code_len("28708").err(), Some(ErrorCode::CodeTooSmall));
+    /// assert_eq!(builder.verify_code_len("2870821").err(), Some(ErrorCode::CodeTooBig));
+    /// ```
+    pub fn verify_code_len(&self, code: &str) -> Result<bool, ErrorCode> {
+        let hotp = self.finalize()?;
+        let code_len = code.chars().count();
+        if code_len < self.output_len {
+            return Err(ErrorCode::CodeTooSmall);
+        }
+        if code_len > self.output_len {
+            return Err(ErrorCode::CodeTooBig);
+        }
+        Ok(hotp.is_valid(code))
+    }
+
+    /// Returns how many counter steps ahead of the configured counter the given code is, without
+    /// modifying the counter. Codes up to `max_lookahead` steps ahead are checked: if none
+    /// matches, `ErrorCode::InvalidKey` is returned.
+    ///
+    /// The whole window is always checked, so the time taken does not reveal the distance. For
+    /// the same reason as `generate_sequence`, `max_lookahead` cannot exceed 10000, otherwise
+    /// `ErrorCode::RangeTooLarge` is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// builder.ascii_key(&key_ascii).counter(1);
+    ///
+    /// assert_eq!(builder.counter_distance("287082", 10).unwrap(), 0);
+    /// assert_eq!(builder.counter_distance("969429", 10).unwrap(), 2);
+    /// assert!(builder.counter_distance("969429", 1).is_err());
+    /// ```
+    pub fn counter_distance(&self, code: &str, max_lookahead: u64) -> Result<u64, ErrorCode> {
+        self.window_verify(code, max_lookahead, 0)
+            .map(|offset| offset as u64)
+    }
+
+    /// Checks the given code against the counters from `look_behind` steps behind the configured
+    /// counter to `look_ahead` steps ahead of it, and returns the signed offset of the matching
+    /// counter: `0` if the code matches the configured counter, `-1` if it matches the previous
+    /// one, `3` if it matches the counter three steps ahead, etc. If several counters match, the
+    /// closest one is returned. If none matches, `ErrorCode::InvalidKey` is returned. The
+    /// configured counter is not modified.
+    ///
+    /// The whole window is always checked, so the time taken does not reveal the offset. Neither
+    /// `look_ahead` nor `look_behind` can exceed 10000, otherwise `ErrorCode::RangeTooLarge` is
+    /// returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// builder.ascii_key(&key_ascii).counter(2);
+    ///
+    /// assert_eq!(builder.window_verify("359152", 2, 2).unwrap(), 0);
+    /// assert_eq!(builder.window_verify("287082", 2, 2).unwrap(), -1);
+    /// assert_eq!(builder.window_verify("338314", 2, 2).unwrap(), 2);
+    /// assert!(builder.window_verify("755224", 2, 1).is_err());
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, code),
+            fields(
+                hash_function = %self.hash_function,
+                output_len = self.output_len,
+                counter = self.counter
+            )
+        )
+    )]
+    pub fn window_verify(
+        &self,
+        code: &str,
+        look_ahead: u64,
+        look_behind: u64,
+    ) -> Result<i64, ErrorCode> {
+        if look_ahead > MAX_SEQUENCE_LEN as u64 || look_behind > MAX_SEQUENCE_LEN as u64 {
+            return Err(ErrorCode::RangeTooLarge);
+        }
+        let hotp = self.finalize()?;
+        let mut found = Choice::from(0);
+        let mut best_distance = 0u64;
+        let mut best_counter = 0u64;
+        let first = self.counter.saturating_sub(look_behind);
+        let last = self.counter.saturating_add(look_ahead);
+        for counter in first..=last {
+            let distance = counter.max(self.counter) - counter.min(self.counter);
+            let is_match = Choice::from(hotp.check_at(code, counter) as u8);
+            let is_better = is_match & (!found | distance.ct_lt(&best_distance));
+            best_distance.conditional_assign(&distance, is_better);
+            best_counter.conditional_assign(&counter, is_better);
+            found |= is_match;
+        }
+        count_verification!("hotp", bool::from(found));
+        if !bool::from(found) {
+            return Err(ErrorCode::InvalidKey);
+        }
+        if best_counter >= self.counter {
+            Ok(best_distance as i64)
+        } else {
+            Ok(-(best_distance as i64))
+        }
+    }
+
+    /// Checks the given code against every counter from `start` to `end`, both included, and
+    /// returns the first matching counter or `None` if none matches (which is the case if `end`
+    /// is lower than `start`). Unlike [window_verify](#method.window_verify), the range does not
+    /// depend on the configured counter, which is ignored: this is meant for administrative
+    /// recovery, when a device's counter is far ahead of the one known by the server.
+    ///
+    /// The whole range is always checked, so the time taken does not reveal the matching counter.
+    /// To prevent abuse, `end - start` cannot exceed 1000, otherwise `ErrorCode::RangeTooLarge`
+    /// is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut builder = libreauth::oath::HOTPBuilder::new();
+    /// builder.ascii_key(&key_ascii);
+    ///
+    /// assert_eq!(builder.verify_at_counter_range("969429", 2, 5).unwrap(), Some(3));
+    /// assert_eq!(builder.verify_at_counter_range("969429", 4, 5).unwrap(), None);
+    /// ```
+    pub fn verify_at_counter_range(
+        &self,
+        code: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<u64>, ErrorCode> {
+        if end.saturating_sub(start) > MAX_COUNTER_RANGE {
+            return Err(ErrorCode::RangeTooLarge);
+        }
+        let hotp = self.finalize()?;
+        let mut found = Choice::from(0);
+        let mut first_counter = 0u64;
+        for counter in start..=end {
+            let is_match = Choice::from(hotp.check_at(code, counter) as u8);
+            first_counter.conditional_assign(&counter, is_match & !found);
+            found |= is_match;
+        }
+        count_verification!("hotp", bool::from(found));
+        if bool::from(found) {
+            Ok(Some(first_counter))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Checks a list of `(code, counter)` pairs against the configured key, hash function and
+    /// output format, and returns the validity of each pair. The internal counter is ignored. If
+    /// the configuration is invalid, every result is the corresponding error.
+    ///
+    /// Each code is checked using the same double HMAC verification as
+    /// [HOTP::is_valid](struct.HOTP.html#method.is_valid). The pairs are checked one after
+    /// the other for now, batching or vectorizing the HMAC computations being left for a future
+    /// version.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let results = libreauth::oath::HOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .verify_all(&[("755224", 0), ("755224", 1), ("287082", 1)]);
+    ///
+    /// assert_eq!(results, vec![Ok(true), Ok(false), Ok(true)]);
+    /// ```
+    pub fn verify_all<T: AsRef<str>>(&self, pairs: &[(T, u64)]) -> Vec<Result<bool, ErrorCode>> {
+        let hotp = match self.finalize() {
+            Ok(hotp) => hotp,
+            Err(e) => return vec![Err(e); pairs.len()],
+        };
+        pairs
+            .iter()
+            .map(|(code, counter)| Ok(hotp.is_valid_at(code.as_ref(), *counter)))
+            .collect()
+    }
+}
+
+impl From<TOTPBuilder> for HOTPBuilder {
+    /// Creates an HOTP builder sharing the key, hash function, output length and output base of a
+    /// TOTP builder. The counter is set to 0.
+    fn from(builder: TOTPBuilder) -> Self {
+        HOTPBuilder {
+            key: builder.key,
+            output_len: builder.output_len,
+            output_base: builder.output_base,
+            hash_function: builder.hash_function,
+            runtime_error: builder.runtime_error,
+            ..HOTPBuilder::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HOTPBuilder;
+    #[cfg(feature = "debug")]
+    use super::HOTPDebugInfo;
+    use crate::hash::HashFunction;
+    #[cfg(feature = "oath-uri")]
+    use crate::oath::ParametersVisibility;
+    use crate::oath::TOTPBuilder;
+    use crate::oath::{Encoding, ErrorCode, ValidationErrors};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_hotp_key_simple() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let hotp = HOTPBuilder::new().key(&key).finalize().unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 6);
+        match hotp.hash_function {
+            HashFunction::Sha1 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 6);
+        assert_eq!(code, "755224");
+    }
+
+    #[test]
+    fn test_hotp_key_full() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let hotp = HOTPBuilder::new()
+            .key(&key)
+            .counter(5)
+            .output_len(8)
+            .hash_function(HashFunction::Sha256)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 5);
+        assert_eq!(hotp.output_len, 8);
+        match hotp.hash_function {
+            HashFunction::Sha256 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 8);
+        assert_eq!(code, "89697997");
+    }
+
+    #[test]
+    fn test_hotp_asciikey_simple() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let hotp = HOTPBuilder::new().ascii_key(&key_ascii).finalize().unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 6);
+        match hotp.hash_function {
+            HashFunction::Sha1 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 6);
+        assert_eq!(code, "755224");
+    }
+
+    #[test]
+    fn test_hotp_asciikey_full() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let hotp = HOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .counter(5)
+            .output_len(8)
+            .hash_function(HashFunction::Sha256)
+            .finalize()
             .unwrap();
 
         assert_eq!(hotp.key, key);
@@ -417,217 +1123,1042 @@ mod tests {
     }
 
     #[test]
-    fn test_hotp_asciikey_simple() {
-        let key_ascii = "12345678901234567890".to_owned();
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+    fn test_hotp_hexkey_simple() {
+        let key_hex = "3132333435363738393031323334353637383930".to_owned();
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let hotp = HOTPBuilder::new().hex_key(&key_hex).finalize().unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 6);
+        match hotp.hash_function {
+            HashFunction::Sha1 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 6);
+        assert_eq!(code, "755224");
+    }
+
+    #[test]
+    fn test_hotp_hexkey_full() {
+        let key_hex = "3132333435363738393031323334353637383930".to_owned();
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let hotp = HOTPBuilder::new()
+            .hex_key(&key_hex)
+            .counter(5)
+            .output_len(8)
+            .hash_function(HashFunction::Sha512)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 5);
+        assert_eq!(hotp.output_len, 8);
+        match hotp.hash_function {
+            HashFunction::Sha512 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 8);
+        assert_eq!(code, "16848329");
+    }
+
+    #[test]
+    fn test_hotp_base32key_simple() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+        let key_base32 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned();
+
+        let hotp = HOTPBuilder::new()
+            .base32_key(&key_base32)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 6);
+        match hotp.hash_function {
+            HashFunction::Sha1 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 6);
+        assert_eq!(code, "755224");
+    }
+
+    #[test]
+    fn test_hotp_base32key_full() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+        let key_base32 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned();
+
+        let hotp = HOTPBuilder::new()
+            .base32_key(&key_base32)
+            .counter(5)
+            .output_len(8)
+            .hash_function(HashFunction::Sha512)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 5);
+        assert_eq!(hotp.output_len, 8);
+        match hotp.hash_function {
+            HashFunction::Sha512 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 8);
+        assert_eq!(code, "16848329");
+    }
+
+    #[test]
+    fn test_hotp_base64key_simple() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+        let key_base64 = "MTIzNDU2Nzg5MDEyMzQ1Njc4OTA=".to_owned();
+
+        let hotp = HOTPBuilder::new()
+            .base64_key(&key_base64)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 6);
+        match hotp.hash_function {
+            HashFunction::Sha1 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 6);
+        assert_eq!(code, "755224");
+    }
+
+    #[test]
+    fn test_hotp_base64key_full() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+        let key_base64 = "MTIzNDU2Nzg5MDEyMzQ1Njc4OTA=".to_owned();
+
+        let hotp = HOTPBuilder::new()
+            .base64_key(&key_base64)
+            .counter(5)
+            .output_len(8)
+            .hash_function(HashFunction::Sha512)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(hotp.key, key);
+        assert_eq!(hotp.counter, 5);
+        assert_eq!(hotp.output_len, 8);
+        match hotp.hash_function {
+            HashFunction::Sha512 => assert!(true),
+            _ => assert!(false),
+        }
+
+        let code = hotp.generate();
+        assert_eq!(code.len(), 8);
+        assert_eq!(code, "16848329");
+    }
+
+    #[test]
+    fn test_hotp_key_encoded() {
+        let keys = [
+            ("12345678901234567890", Encoding::Ascii),
+            ("3132333435363738393031323334353637383930", Encoding::Hex),
+            ("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ", Encoding::Base32),
+            ("MTIzNDU2Nzg5MDEyMzQ1Njc4OTA=", Encoding::Base64),
+            ("MTIzNDU2Nzg5MDEyMzQ1Njc4OTA", Encoding::Base64Url),
+        ];
+        for (key, encoding) in keys.iter() {
+            let hotp = HOTPBuilder::new()
+                .set_key_encoded(key, *encoding)
+                .finalize()
+                .unwrap();
+            assert_eq!(hotp.generate(), "755224");
+        }
+        let res = HOTPBuilder::new()
+            .set_key_encoded("!@#$%^&", Encoding::Base64)
+            .finalize();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_generate_at() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut hotp = HOTPBuilder::new().ascii_key(&key_ascii).finalize().unwrap();
+        let rfc_codes = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in rfc_codes.iter().enumerate() {
+            assert_eq!(&hotp.generate_at(counter as u64), code);
+            assert!(hotp.is_valid_at(code, counter as u64));
+            assert!(!hotp.is_valid_at(code, counter as u64 + 1));
+        }
+        hotp.increment_counter();
+        assert_eq!(hotp.generate_at(0), "755224");
+        assert_eq!(hotp.generate(), "287082");
+    }
+
+    #[test]
+    fn test_verify_list() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let hotp = HOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .counter(1)
+            .finalize()
+            .unwrap();
+        assert_eq!(hotp.verify_list(&["287082"]), Some(0));
+        assert_eq!(hotp.verify_list(&["", "755224", "287082"]), Some(2));
+        assert_eq!(hotp.verify_list(&["287082", "755224", "287082"]), Some(0));
+        assert_eq!(hotp.verify_list(&["755224", "359152", "2870820"]), None);
+        assert_eq!(hotp.verify_list(&[]), None);
+    }
+
+    #[test]
+    fn test_from_totp_builder() {
+        let mut totp = TOTPBuilder::new();
+        totp.ascii_key("12345678901234567890")
+            .period(60)
+            .output_len(8)
+            .hash_function(HashFunction::Sha256);
+        let hotp = HOTPBuilder::from(totp).finalize().unwrap();
+        assert_eq!(hotp.key, b"12345678901234567890".to_vec());
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 8);
+        assert_eq!(hotp.output_base, "0123456789");
+        assert!(hotp.hash_function == HashFunction::Sha256);
+        let reference = HOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .output_len(8)
+            .hash_function(HashFunction::Sha256)
+            .finalize()
+            .unwrap();
+        assert_eq!(hotp.generate(), reference.generate());
+
+        let mut totp = TOTPBuilder::new();
+        totp.ascii_key("12345678901234567890").output_len(3);
+        assert!(HOTPBuilder::from(totp).finalize().is_err());
+    }
+
+    #[test]
+    fn test_nokey() {
+        match HOTPBuilder::new().finalize() {
+            Ok(_) => assert!(false),
+            Err(_) => assert!(true),
+        }
+    }
+
+    #[test]
+    fn test_error_code_eq() {
+        assert_eq!(
+            HOTPBuilder::new().finalize().err(),
+            Some(ErrorCode::InvalidKey)
+        );
+        assert_ne!(
+            HOTPBuilder::new()
+                .hex_key("12")
+                .output_len(5)
+                .finalize()
+                .err(),
+            Some(ErrorCode::InvalidKey)
+        );
+
+        let mut errors = std::collections::HashMap::new();
+        for _ in 0..3 {
+            if let Err(e) = HOTPBuilder::new().hex_key("g").finalize() {
+                *errors.entry(e).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(errors.get(&ErrorCode::InvalidKey), Some(&3));
+    }
+
+    #[test]
+    fn test_generate_sequence() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key(&key_ascii).counter(42);
+        let codes = builder.generate_sequence(0, 10).unwrap();
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        assert_eq!(codes, expected);
+        assert_eq!(builder.generate_sequence(3, 2).unwrap(), &expected[3..5]);
+        assert!(builder.generate_sequence(7, 0).unwrap().is_empty());
+        assert_eq!(builder.generate_sequence(0, 10_000).unwrap().len(), 10_000);
+        assert_eq!(
+            builder.generate_sequence(0, 10_001),
+            Err(ErrorCode::RangeTooLarge)
+        );
+
+        let max = u64::max_value();
+        assert_eq!(builder.generate_sequence(max, 1).unwrap().len(), 1);
+        assert_eq!(builder.generate_sequence(max - 1, 2).unwrap().len(), 2);
+        assert!(builder.generate_sequence(max, 0).unwrap().is_empty());
+        assert_eq!(
+            builder.generate_sequence(max, 2),
+            Err(ErrorCode::CounterOverflow)
+        );
+
+        assert_eq!(
+            HOTPBuilder::new().generate_sequence(0, 1),
+            Err(ErrorCode::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_counter_distance() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key(&key_ascii).counter(3);
+        let examples = [("969429", 0), ("338314", 1), ("254676", 2), ("520489", 6)];
+        for &(code, distance) in examples.iter() {
+            assert_eq!(builder.counter_distance(code, 6), Ok(distance));
+        }
+        assert_eq!(builder.counter, 3);
+        assert_eq!(
+            builder.counter_distance("520489", 5),
+            Err(ErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            builder.counter_distance("359152", 6),
+            Err(ErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            builder.counter_distance("969429", 10_001),
+            Err(ErrorCode::RangeTooLarge)
+        );
+
+        let code = builder.generate_sequence(u64::max_value(), 1).unwrap();
+        builder.counter(u64::max_value() - 1);
+        assert_eq!(builder.counter_distance(&code[0], 10), Ok(1));
+
+        assert_eq!(
+            HOTPBuilder::new().counter_distance("755224", 1),
+            Err(ErrorCode::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_window_verify() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key(&key_ascii).counter(4);
+        let codes = builder.generate_sequence(0, 10).unwrap();
+        for (counter, code) in codes.iter().enumerate() {
+            let offset = counter as i64 - 4;
+            let expected = if offset >= -4 && offset <= 3 {
+                Ok(offset)
+            } else {
+                Err(ErrorCode::InvalidKey)
+            };
+            assert_eq!(builder.window_verify(code, 3, 4), expected);
+        }
+        assert_eq!(builder.counter, 4);
+        assert_eq!(builder.window_verify(&codes[4], 0, 0), Ok(0));
+        assert_eq!(
+            builder.window_verify(&codes[3], 0, 0),
+            Err(ErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            builder.window_verify(&codes[4], 10_001, 0),
+            Err(ErrorCode::RangeTooLarge)
+        );
+        assert_eq!(
+            builder.window_verify(&codes[4], 0, 10_001),
+            Err(ErrorCode::RangeTooLarge)
+        );
+
+        // The window is truncated at 0.
+        builder.counter(1);
+        assert_eq!(builder.window_verify(&codes[0], 0, 10), Ok(-1));
+
+        assert_eq!(
+            HOTPBuilder::new().window_verify("755224", 1, 1),
+            Err(ErrorCode::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_verify_all() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let pairs = vec![
+            ("755224".to_string(), 0),
+            ("287082".to_string(), 1),
+            ("359152".to_string(), 1),
+            ("359152".to_string(), 2),
+            ("".to_string(), 2),
+        ];
+        let results = HOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .counter(5)
+            .verify_all(&pairs);
+        assert_eq!(
+            results,
+            vec![Ok(true), Ok(true), Ok(false), Ok(true), Ok(false)]
+        );
+
+        let results = HOTPBuilder::new().verify_all(&pairs[..2]);
+        assert_eq!(
+            results,
+            vec![Err(ErrorCode::InvalidKey), Err(ErrorCode::InvalidKey)]
+        );
+
+        let empty: [(&str, u64); 0] = [];
+        assert!(HOTPBuilder::new().verify_all(&empty).is_empty());
+    }
+
+    #[test]
+    fn test_hotp_base64url_key() {
+        let mut builder = HOTPBuilder::new();
+        builder.base64url_key("-_-_");
+        assert_eq!(builder.key, Some(vec![0xfb, 0xff, 0xbf]));
+
+        // Symmetric key of the RFC 7517 appendix A.3 example.
+        let key = vec![
+            0x19, 0xac, 0x20, 0x82, 0xe1, 0x72, 0x1a, 0xb5, 0x8a, 0x6a, 0xfe, 0xc0, 0x5f, 0x85,
+            0x4a, 0x52,
+        ];
+        let reference = HOTPBuilder::new().key(&key).finalize().unwrap().generate();
+        let code = HOTPBuilder::new()
+            .base64url_key("GawgguFyGrWKav7AX4VKUg")
+            .finalize()
+            .unwrap()
+            .generate();
+        assert_eq!(code, reference);
+
+        for key in ["+/+/", "GawgguFyGrWKav7AX4VKU!"].iter() {
+            let res = HOTPBuilder::new().base64url_key(key).finalize();
+            assert_eq!(res.err(), Some(ErrorCode::InvalidKey));
+        }
+    }
+
+    #[test]
+    fn test_hotp_base32key_padded() {
+        let reference = HOTPBuilder::new()
+            .base32_key("JBSWY3DPEHPK3PXP")
+            .finalize()
+            .unwrap()
+            .generate();
+        for key in [
+            "JBSWY3DPEHPK3PXP",
+            "JBSWY3DPEHPK3PXP====",
+            "jbswy3dpehpk3pxp",
+        ]
+        .iter()
+        {
+            let code = HOTPBuilder::new()
+                .base32_key_padded(key)
+                .finalize()
+                .unwrap()
+                .generate();
+            assert_eq!(code, reference);
+        }
+
+        let reference = HOTPBuilder::new()
+            .base32_key("MZXW6YQ")
+            .finalize()
+            .unwrap()
+            .generate();
+        let code = HOTPBuilder::new()
+            .base32_key_padded("MZXW6YQ=")
+            .finalize()
+            .unwrap()
+            .generate();
+        assert_eq!(code, reference);
+
+        let mut builder = HOTPBuilder::new();
+        builder.base32_key_padded("JBSWY3DP=EHPK3PXP");
+        assert_eq!(builder.runtime_error, Some(ErrorCode::InvalidKey));
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut builder = HOTPBuilder::new();
+        builder
+            .ascii_key(&key_ascii)
+            .counter(42)
+            .output_len(7)
+            .output_base("0123456789abcdef")
+            .hash_function(HashFunction::Sha256);
+        let config = builder.to_config().unwrap();
+        assert_eq!(config.key, key_ascii.as_bytes());
+        assert_eq!(config.counter, 42);
+        assert_eq!(config.output_len, 7);
+        assert_eq!(config.output_base, "0123456789abcdef");
+        assert!(config.hash_function == HashFunction::Sha256);
+
+        let restored = HOTPBuilder::from_config(&config).unwrap();
+        assert_eq!(
+            restored.finalize().unwrap().generate(),
+            builder.finalize().unwrap().generate()
+        );
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert_eq!(
+            HOTPBuilder::new().to_config().err(),
+            Some(ErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            HOTPBuilder::new().hex_key("zz").to_config().err(),
+            Some(ErrorCode::InvalidKey)
+        );
+
+        let config = HOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .to_config()
+            .unwrap();
+        let mut invalid = config.clone();
+        invalid.output_len = 2;
+        assert_eq!(
+            HOTPBuilder::from_config(&invalid).err(),
+            Some(ErrorCode::CodeTooSmall)
+        );
+        let mut invalid = config;
+        invalid.output_len = 42;
+        assert_eq!(
+            HOTPBuilder::from_config(&invalid).err(),
+            Some(ErrorCode::CodeTooBig)
+        );
+    }
+
+    #[test]
+    fn test_algorithm_suite() {
+        let mut builder = HOTPBuilder::new();
+        assert_eq!(builder.algorithm_suite(), "HOTP-SHA1-6");
+        builder.hash_function(HashFunction::Sha512).output_len(9);
+        assert_eq!(builder.algorithm_suite(), "HOTP-SHA512-9");
+        builder
+            .hash_function(HashFunction::Keccak256)
+            .output_len(7)
+            .ascii_key("12345678901234567890");
+        assert_eq!(builder.algorithm_suite(), "HOTP-Keccak256-7");
+    }
+
+    #[test]
+    #[cfg(feature = "debug")]
+    fn test_truncate_to_u32() {
+        // RFC 4226 appendix D.
+        let examples: [u32; 10] = [
+            1284755224, 1094287082, 137359152, 1726969429, 1640338314, 868254676, 1918287922,
+            82162583, 673399871, 645520489,
+        ];
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key("12345678901234567890");
+        for (counter, value) in examples.iter().enumerate() {
+            let res = builder.counter(counter as u64).truncate_to_u32();
+            assert_eq!(res, Ok(*value));
+            assert!(*value <= 0x7fff_ffff);
+        }
+
+        let res = HOTPBuilder::new().truncate_to_u32();
+        assert_eq!(res, Err(ErrorCode::InvalidKey));
+    }
+
+    #[test]
+    #[cfg(feature = "debug")]
+    fn test_debug_info() {
+        // RFC 4226 appendix D.
+        let examples = [
+            (
+                "cc93cf18508d94934c64b65d8ba7667fb7cde4b0",
+                0,
+                1284755224,
+                "755224",
+            ),
+            (
+                "75a48a19d4cbe100644e8ac1397eea747a2d33ab",
+                11,
+                1094287082,
+                "287082",
+            ),
+            (
+                "0bacb7fa082fef30782211938bc1c5e70416ff44",
+                4,
+                137359152,
+                "359152",
+            ),
+            (
+                "66c28227d03a2d5529262ff016a1e6ef76557ece",
+                14,
+                1726969429,
+                "969429",
+            ),
+            (
+                "a904c900a64b35909874b33e61c5938a8e15ed1c",
+                12,
+                1640338314,
+                "338314",
+            ),
+            (
+                "a37e783d7b7233c083d4f62926c7a25f238d0316",
+                6,
+                868254676,
+                "254676",
+            ),
+            (
+                "bc9cd28561042c83f219324d3c607256c03272ae",
+                14,
+                1918287922,
+                "287922",
+            ),
+            (
+                "a4fb960c0bc06e1eabb804e5b397cdc4b45596fa",
+                10,
+                82162583,
+                "162583",
+            ),
+            (
+                "1b3c89f65e6c9e883012052823443f048b4332db",
+                11,
+                673399871,
+                "399871",
+            ),
+            (
+                "1637409809a679dc698207310c8c7fc07290d9e5",
+                5,
+                645520489,
+                "520489",
+            ),
+        ];
+        let key_ascii = "12345678901234567890".to_owned();
+        for (counter, &(hmac, offset, truncated_value, code)) in examples.iter().enumerate() {
+            let debug_info = HOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .counter(counter as u64)
+                .debug_info()
+                .unwrap();
+            assert_eq!(
+                debug_info,
+                HOTPDebugInfo {
+                    counter: counter as u64,
+                    hmac: hmac.to_string(),
+                    offset,
+                    truncated_value,
+                    code: code.to_string(),
+                }
+            );
+        }
+        assert_eq!(
+            HOTPBuilder::new().debug_info().err(),
+            Some(ErrorCode::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_verify_strict() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key(&key_ascii).counter(2);
+        assert_eq!(builder.verify_strict("359152"), Ok(true));
+        assert_eq!(builder.verify_strict("287082"), Ok(false));
+        assert_eq!(builder.verify_strict("969429"), Ok(false));
+        assert_eq!(builder.verify_strict(""), Ok(false));
+        assert_eq!(
+            HOTPBuilder::new().verify_strict("359152"),
+            Err(ErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            HOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .output_len(2)
+                .verify_strict("59"),
+            Err(ErrorCode::CodeTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_error_code_ord() {
+        assert!(ErrorCode::Success < ErrorCode::NullPtr);
+        assert!(ErrorCode::CodeTooBig > ErrorCode::CodeTooSmall);
+        assert!(ErrorCode::CounterOverflow > ErrorCode::InvalidUri);
+        assert!(ErrorCode::WeakKey <= ErrorCode::WeakKey);
+        let mut errors = vec![
+            ErrorCode::InvalidKey,
+            ErrorCode::ClockError,
+            ErrorCode::InvalidBaseLen,
         ];
+        assert_eq!(errors.iter().max(), Some(&ErrorCode::ClockError));
+        errors.sort();
+        assert_eq!(
+            errors,
+            vec![
+                ErrorCode::InvalidBaseLen,
+                ErrorCode::InvalidKey,
+                ErrorCode::ClockError
+            ]
+        );
+    }
 
-        let hotp = HOTPBuilder::new().ascii_key(&key_ascii).finalize().unwrap();
+    #[test]
+    fn test_validation_errors() {
+        let errors = ValidationErrors::new();
+        assert!(errors.is_empty());
+        assert_eq!(errors, ValidationErrors::default());
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 0);
-        assert_eq!(hotp.output_len, 6);
-        match hotp.hash_function {
-            HashFunction::Sha1 => assert!(true),
-            _ => assert!(false),
-        }
+        let errors = errors | ErrorCode::InvalidKeyLen | ErrorCode::InvalidPeriod;
+        assert!(!errors.is_empty());
+        assert_eq!(
+            errors.as_slice(),
+            &[ErrorCode::InvalidKeyLen, ErrorCode::InvalidPeriod]
+        );
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 6);
-        assert_eq!(code, "755224");
+        let other = ValidationErrors::from(ErrorCode::InvalidPeriod) | ErrorCode::WeakKey;
+        let mut errors = errors | other;
+        assert_eq!(
+            errors.as_slice(),
+            &[
+                ErrorCode::InvalidKeyLen,
+                ErrorCode::InvalidPeriod,
+                ErrorCode::WeakKey
+            ]
+        );
+        errors |= ErrorCode::InvalidKey;
+        let errors: Vec<ErrorCode> = errors.into();
+        assert_eq!(
+            errors,
+            vec![
+                ErrorCode::InvalidKeyLen,
+                ErrorCode::InvalidPeriod,
+                ErrorCode::WeakKey,
+                ErrorCode::InvalidKey
+            ]
+        );
     }
 
     #[test]
-    fn test_hotp_asciikey_full() {
-        let key_ascii = "12345678901234567890".to_owned();
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
+    fn test_validate_key() {
+        let mut builder = HOTPBuilder::new();
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::InvalidKey]));
+        builder.ascii_key("12345678901234567890");
+        assert_eq!(builder.validate_key(), Ok(()));
+        builder.ascii_key("123456789012345");
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::InvalidKeyLen]));
+        builder.key(&[0; 20]);
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::WeakKey]));
+        builder.ascii_key("abcdabcdabcdabcdab");
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::WeakKey]));
+        builder.key(&[0; 4]);
+        assert_eq!(
+            builder.validate_key(),
+            Err(vec![ErrorCode::InvalidKeyLen, ErrorCode::WeakKey])
+        );
+        builder.hex_key("zz");
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::InvalidKey]));
 
-        let hotp = HOTPBuilder::new()
-            .ascii_key(&key_ascii)
-            .counter(5)
-            .output_len(8)
-            .hash_function(HashFunction::Sha256)
+        let mut builder = TOTPBuilder::new();
+        builder.base32_key("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(builder.validate_key(), Ok(()));
+    }
+
+    #[test]
+    fn test_try_from_key_and_hash() {
+        let key = b"12345678901234567890".to_vec();
+        let hotp = HOTPBuilder::try_from((key.clone(), HashFunction::Sha1))
+            .unwrap()
             .finalize()
             .unwrap();
-
         assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 5);
-        assert_eq!(hotp.output_len, 8);
-        match hotp.hash_function {
-            HashFunction::Sha256 => assert!(true),
-            _ => assert!(false),
-        }
+        assert_eq!(hotp.counter, 0);
+        assert_eq!(hotp.output_len, 6);
+        assert_eq!(hotp.output_base, "0123456789");
+        assert_eq!(hotp.generate(), "755224");
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 8);
-        assert_eq!(code, "89697997");
+        let builder = HOTPBuilder::try_from((key[..16].to_vec(), HashFunction::Sha512));
+        assert!(builder.unwrap().hash_function == HashFunction::Sha512);
+        let builder = HOTPBuilder::try_from((key[..15].to_vec(), HashFunction::Sha1));
+        assert_eq!(builder.err(), Some(ErrorCode::InvalidKeyLen));
     }
 
     #[test]
-    fn test_hotp_hexkey_simple() {
-        let key_hex = "3132333435363738393031323334353637383930".to_owned();
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
-
-        let hotp = HOTPBuilder::new().hex_key(&key_hex).finalize().unwrap();
-
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 0);
-        assert_eq!(hotp.output_len, 6);
-        match hotp.hash_function {
-            HashFunction::Sha1 => assert!(true),
-            _ => assert!(false),
+    fn test_verify_constant_time() {
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key("12345678901234567890").counter(1);
+        assert_eq!(builder.verify_constant_time("287082"), Ok(true));
+        for code in ["287083", "755224", "28708", "2870822", "", "28708é"].iter() {
+            assert_eq!(builder.verify_constant_time(code), Ok(false));
         }
-
-        let code = hotp.generate();
-        assert_eq!(code.len(), 6);
-        assert_eq!(code, "755224");
+        builder.output_len(2);
+        assert_eq!(
+            builder.verify_constant_time("287082"),
+            Err(ErrorCode::CodeTooSmall)
+        );
     }
 
     #[test]
-    fn test_hotp_hexkey_full() {
-        let key_hex = "3132333435363738393031323334353637383930".to_owned();
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
+    fn test_verify_code_len() {
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key("12345678901234567890").output_len(8);
+        assert_eq!(builder.verify_code_len("84755224"), Ok(true));
+        assert_eq!(builder.verify_code_len("84755225"), Ok(false));
+        assert_eq!(
+            builder.verify_code_len("4755224"),
+            Err(ErrorCode::CodeTooSmall)
+        );
+        assert_eq!(builder.verify_code_len(""), Err(ErrorCode::CodeTooSmall));
+        assert_eq!(
+            builder.verify_code_len("847552240"),
+            Err(ErrorCode::CodeTooBig)
+        );
 
-        let hotp = HOTPBuilder::new()
-            .hex_key(&key_hex)
-            .counter(5)
-            .output_len(8)
-            .hash_function(HashFunction::Sha512)
-            .finalize()
-            .unwrap();
+        builder.output_len(2);
+        assert_eq!(builder.verify_code_len("84"), Err(ErrorCode::CodeTooSmall));
+    }
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 5);
-        assert_eq!(hotp.output_len, 8);
-        match hotp.hash_function {
-            HashFunction::Sha512 => assert!(true),
-            _ => assert!(false),
+    #[test]
+    fn test_verify_at_counter_range() {
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key("12345678901234567890").counter(42);
+        let codes = builder.generate_sequence(0, 10).unwrap();
+        for (counter, code) in codes.iter().enumerate() {
+            let counter = counter as u64;
+            assert_eq!(
+                builder.verify_at_counter_range(code, 0, 9),
+                Ok(Some(counter))
+            );
+            assert_eq!(
+                builder.verify_at_counter_range(code, counter, counter),
+                Ok(Some(counter))
+            );
+            assert_eq!(builder.verify_at_counter_range(code, 10, 20), Ok(None));
         }
+        assert_eq!(builder.verify_at_counter_range(&codes[5], 9, 0), Ok(None));
+        assert_eq!(builder.verify_at_counter_range("123456", 0, 9), Ok(None));
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 8);
-        assert_eq!(code, "16848329");
+        let far = builder.generate_sequence(1_000_000, 1).unwrap();
+        assert_eq!(
+            builder.verify_at_counter_range(&far[0], 999_500, 1_000_500),
+            Ok(Some(1_000_000))
+        );
+        assert_eq!(
+            builder.verify_at_counter_range(&far[0], 999_500, 1_000_501),
+            Err(ErrorCode::RangeTooLarge)
+        );
+        assert_eq!(
+            builder.verify_at_counter_range(&far[0], 0, u64::max_value()),
+            Err(ErrorCode::RangeTooLarge)
+        );
+        assert_eq!(
+            builder.verify_at_counter_range("755224", u64::max_value() - 1, u64::max_value()),
+            Ok(None)
+        );
+
+        assert_eq!(
+            HOTPBuilder::new().verify_at_counter_range("755224", 0, 1),
+            Err(ErrorCode::InvalidKey)
+        );
     }
 
     #[test]
-    fn test_hotp_base32key_simple() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
-        let key_base32 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned();
+    fn test_owned_arguments() {
+        // Callers holding a `Vec<u8>` or a `String` pass a reference to it, which is coerced to
+        // the `&[u8]` or `&str` parameters.
+        let key: Vec<u8> = b"12345678901234567890".to_vec();
+        let base32_key: String = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string();
+        let output_base: String = "0123456789".to_string();
+        let code: String = "755224".to_string();
 
         let hotp = HOTPBuilder::new()
-            .base32_key(&key_base32)
+            .key(&key)
+            .output_base(&output_base)
+            .finalize()
+            .unwrap();
+        assert!(hotp.is_valid(&code));
+        let hotp = HOTPBuilder::new()
+            .base32_key(&base32_key)
             .finalize()
             .unwrap();
+        assert_eq!(hotp.generate(), code);
+    }
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 0);
-        assert_eq!(hotp.output_len, 6);
-        match hotp.hash_function {
-            HashFunction::Sha1 => assert!(true),
-            _ => assert!(false),
-        }
+    #[test]
+    fn test_key_as_hex_display() {
+        let mut builder = HOTPBuilder::new();
+        assert!(builder.key_as_hex_display().is_none());
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 6);
-        assert_eq!(code, "755224");
+        builder.key(&[0x00, 0x0f, 0xab, 0xff]);
+        let key = builder.key_as_hex_display().unwrap();
+        assert_eq!(format!("{:x}", key), "000fabff");
+        assert_eq!(format!("{:X}", key), "000FABFF");
+        assert_eq!(format!("{:?}", key), "HexKey(***)");
+
+        builder.key(&[]);
+        assert_eq!(format!("{:x}", builder.key_as_hex_display().unwrap()), "");
     }
 
     #[test]
-    fn test_hotp_base32key_full() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+    fn test_key_len() {
+        let mut builder = HOTPBuilder::new();
+        assert_eq!(builder.key_len(), None);
+        builder.hex_key("3132333435363738393031323334353637383930");
+        assert_eq!(builder.key_len(), Some(20));
+        builder.key(&[]);
+        assert_eq!(builder.key_len(), Some(0));
+
+        let mut builder = TOTPBuilder::new();
+        assert_eq!(builder.key_len(), None);
+        builder.ascii_key("12345678901234567890123456789012");
+        assert_eq!(builder.key_len(), Some(32));
+    }
+
+    #[test]
+    fn test_auto_key() {
+        let key = b"12345678901234567890".to_vec();
+        let cases = [
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            "gezdgnbvgy3tqojqgezdgnbvgy3tqojq",
+            "3132333435363738393031323334353637383930",
         ];
-        let key_base32 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned();
+        for k in cases.iter() {
+            let config = HOTPBuilder::new().auto_key(k).to_config().unwrap();
+            assert_eq!(config.key, key, "{}", k);
+        }
 
-        let hotp = HOTPBuilder::new()
-            .base32_key(&key_base32)
-            .counter(5)
-            .output_len(8)
-            .hash_function(HashFunction::Sha512)
-            .finalize()
+        let config = HOTPBuilder::new().auto_key("DEADBEEF").to_config().unwrap();
+        assert_eq!(config.key, vec![0x19, 0x00, 0x30, 0x90, 0x85]);
+        let config = HOTPBuilder::new()
+            .auto_key("deadbeef01")
+            .to_config()
             .unwrap();
+        assert_eq!(config.key, vec![0xde, 0xad, 0xbe, 0xef, 0x01]);
+        let config = HOTPBuilder::new()
+            .auto_key("deadbeef0")
+            .to_config()
+            .unwrap();
+        assert_eq!(config.key, b"deadbeef0".to_vec());
+        let config = HOTPBuilder::new()
+            .auto_key("my secret key!")
+            .to_config()
+            .unwrap();
+        assert_eq!(config.key, b"my secret key!".to_vec());
+    }
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 5);
-        assert_eq!(hotp.output_len, 8);
-        match hotp.hash_function {
-            HashFunction::Sha512 => assert!(true),
-            _ => assert!(false),
-        }
+    #[test]
+    fn test_with_key_len() {
+        let mut builder = HOTPBuilder::with_key_len(32);
+        let config = builder.to_config().unwrap();
+        assert_eq!(config.key.len(), 32);
+        assert_ne!(
+            config.key,
+            HOTPBuilder::with_key_len(32).to_config().unwrap().key
+        );
+        assert_eq!(
+            builder
+                .hash_function(HashFunction::Sha256)
+                .finalize()
+                .unwrap()
+                .generate()
+                .len(),
+            6
+        );
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 8);
-        assert_eq!(code, "16848329");
+        let hotp = HOTPBuilder::with_key_len(15).finalize();
+        assert_eq!(hotp.err(), Some(ErrorCode::InvalidKeyLen));
     }
 
     #[test]
-    fn test_hotp_base64key_simple() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
-        let key_base64 = "MTIzNDU2Nzg5MDEyMzQ1Njc4OTA=".to_owned();
+    fn test_minimum_key_len() {
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key("1234567890");
+        assert!(builder.finalize().is_ok());
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::InvalidKeyLen]));
 
-        let hotp = HOTPBuilder::new()
-            .base64_key(&key_base64)
-            .finalize()
-            .unwrap();
+        builder.minimum_key_len(10);
+        assert!(builder.finalize().is_ok());
+        assert_eq!(builder.validate_key(), Ok(()));
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 0);
-        assert_eq!(hotp.output_len, 6);
-        match hotp.hash_function {
-            HashFunction::Sha1 => assert!(true),
-            _ => assert!(false),
-        }
+        builder.minimum_key_len(11);
+        assert_eq!(builder.finalize().err(), Some(ErrorCode::InvalidKeyLen));
+        assert_eq!(builder.validate_key(), Err(vec![ErrorCode::InvalidKeyLen]));
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 6);
-        assert_eq!(code, "755224");
+        builder
+            .minimum_key_len(32)
+            .ascii_key("12345678901234567890");
+        assert_eq!(builder.finalize().err(), Some(ErrorCode::InvalidKeyLen));
+        builder.ascii_key("12345678901234567890123456789012");
+        assert!(builder.finalize().is_ok());
     }
 
     #[test]
-    fn test_hotp_base64key_full() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
-        let key_base64 = "MTIzNDU2Nzg5MDEyMzQ1Njc4OTA=".to_owned();
-
-        let hotp = HOTPBuilder::new()
-            .base64_key(&key_base64)
-            .counter(5)
+    fn test_chained_calls() {
+        let key = b"12345678901234567890";
+        let chained = HOTPBuilder::new()
+            .key(key)
+            .hash_function(HashFunction::Sha256)
             .output_len(8)
-            .hash_function(HashFunction::Sha512)
+            .counter(42)
             .finalize()
-            .unwrap();
+            .unwrap()
+            .generate();
 
-        assert_eq!(hotp.key, key);
-        assert_eq!(hotp.counter, 5);
-        assert_eq!(hotp.output_len, 8);
-        match hotp.hash_function {
-            HashFunction::Sha512 => assert!(true),
-            _ => assert!(false),
-        }
+        let mut builder = HOTPBuilder::new();
+        builder.key(key);
+        builder.hash_function(HashFunction::Sha256);
+        builder.output_len(8);
+        builder.counter(42);
+        let sequential = builder.finalize().unwrap().generate();
 
-        let code = hotp.generate();
-        assert_eq!(code.len(), 8);
-        assert_eq!(code, "16848329");
+        assert_eq!(chained, sequential);
+        assert_eq!(builder.key, Some(key.to_vec()));
+        assert!(builder.hash_function == HashFunction::Sha256);
+        assert_eq!(builder.output_len, 8);
+        assert_eq!(builder.counter, 42);
+        assert_eq!(builder.runtime_error, None);
     }
 
     #[test]
-    fn test_nokey() {
-        match HOTPBuilder::new().finalize() {
-            Ok(_) => assert!(false),
-            Err(_) => assert!(true),
-        }
+    fn test_key_overwrite() {
+        let mut builder = HOTPBuilder::new();
+        builder.ascii_key("first key").key(b"second key");
+        assert_eq!(builder.key, Some(b"second key".to_vec()));
+
+        let reference = HOTPBuilder::new()
+            .key(b"second key")
+            .finalize()
+            .unwrap()
+            .generate();
+        assert_eq!(builder.finalize().unwrap().generate(), reference);
+    }
+
+    #[test]
+    fn test_invalid_hexkey_state() {
+        let mut builder = HOTPBuilder::new();
+        builder.hex_key("!@#$%^&");
+        assert_eq!(builder.runtime_error, Some(ErrorCode::InvalidKey));
+        assert_eq!(builder.key, None);
+
+        // The error is kept even if a valid key is set afterwards.
+        builder.key(b"12345678901234567890");
+        assert_eq!(builder.runtime_error, Some(ErrorCode::InvalidKey));
+        assert_eq!(builder.finalize().err(), Some(ErrorCode::InvalidKey));
+
+        // A valid key followed by an invalid one keeps the valid key.
+        let mut builder = HOTPBuilder::new();
+        builder.key(b"12345678901234567890").hex_key("zz");
+        assert_eq!(builder.runtime_error, Some(ErrorCode::InvalidKey));
+        assert_eq!(builder.key, Some(b"12345678901234567890".to_vec()));
     }
 
     #[test]
@@ -676,6 +2207,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_output_base_errors() {
+        let cases = [
+            ("1", ErrorCode::InvalidBaseLen),
+            ("0120", ErrorCode::InvalidBase),
+            ("0 1", ErrorCode::InvalidBase),
+        ];
+        for (base, error) in cases.iter() {
+            let hotp = HOTPBuilder::new()
+                .ascii_key("12345678901234567890")
+                .output_base(base)
+                .finalize();
+            assert_eq!(hotp.err(), Some(*error), "{}", base);
+        }
+
+        let hotp = HOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .output_base("1")
+            .output_base("0123456789")
+            .finalize();
+        assert!(hotp.is_ok());
+    }
+
     #[test]
     fn test_small_result_base10() {
         let key_ascii = "12345678901234567890".to_owned();
@@ -704,6 +2258,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_code_length_overflow() {
+        let key_ascii = "12345678901234567890".to_owned();
+        for nb in [20, 100, usize::max_value()].iter() {
+            let mut builder = HOTPBuilder::new();
+            builder.ascii_key(&key_ascii).output_len(*nb);
+            assert_eq!(builder.finalize().err(), Some(ErrorCode::CodeTooBig));
+            assert_eq!(builder.verify_strict("123456"), Err(ErrorCode::CodeTooBig));
+            assert_eq!(builder.generate_sequence(0, 1), Err(ErrorCode::CodeTooBig));
+
+            let totp = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .output_len(*nb)
+                .finalize();
+            assert_eq!(totp.err(), Some(ErrorCode::CodeTooBig));
+        }
+    }
+
     #[test]
     fn test_result_ok_base10() {
         let key_ascii = "12345678901234567890".to_owned();
@@ -1261,6 +2833,31 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "oath-uri")]
+    fn test_key_uri_format_unicode_issuer() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let hotp = HOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .counter(42)
+            .finalize()
+            .unwrap();
+
+        let uri = hotp
+            .key_uri_format("Société Générale ☃", "alice@example.com")
+            .finalize();
+
+        assert_eq!(
+            uri,
+            "otpauth://hotp/Soci%C3%A9t%C3%A9%20G%C3%A9n%C3%A9rale%20%E2%98%83:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Soci%C3%A9t%C3%A9+G%C3%A9n%C3%A9rale+%E2%98%83&counter=42"
+        );
+
+        let parsed = url::Url::parse(&uri).unwrap();
+        let params: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+        assert!(params.contains(&("issuer".to_string(), "Société Générale ☃".to_string())));
+        assert!(params.contains(&("counter".to_string(), "42".to_string())));
+    }
+
     #[test]
     #[cfg(feature = "oath-uri")]
     fn test_key_uri_format_hide_all() {
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::ops::Deref;
+use zeroize::Zeroize;
+
+/// A shared secret which is zeroed in memory when dropped.
+///
+/// Since it dereferences to `[u8]`, it can be given to the builders' `key` method. Please note
+/// that only the memory owned by this structure is zeroed: the builders, as well as the HOTP and
+/// TOTP objects, keep their own copy of the key.
+///
+/// ## Examples
+/// ```rust
+/// use libreauth::oath::{HOTPBuilder, KeyMaterial};
+///
+/// let key = KeyMaterial::new(b"12345678901234567890".to_vec());
+/// let code = HOTPBuilder::new().key(&key).finalize().unwrap().generate();
+/// assert_eq!(code, "755224");
+/// ```
+pub struct KeyMaterial(Vec<u8>);
+
+impl KeyMaterial {
+    pub fn new(key: Vec<u8>) -> Self {
+        KeyMaterial(key)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for KeyMaterial {
+    fn from(key: Vec<u8>) -> Self {
+        KeyMaterial::new(key)
+    }
+}
+
+impl From<&[u8]> for KeyMaterial {
+    fn from(key: &[u8]) -> Self {
+        KeyMaterial::new(key.to_vec())
+    }
+}
+
+impl Deref for KeyMaterial {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for KeyMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KeyMaterial(***)")
+    }
+}
+
+impl Drop for KeyMaterial {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// A copy of a shared secret which is formatted as hexadecimal using `{:x}` or `{:X}` and is
+/// zeroed in memory when dropped.
+///
+/// ## Examples
+/// ```rust
+/// let key = libreauth::oath::HOTPBuilder::new()
+///     .ascii_key("12345678901234567890")
+///     .key_as_hex_display()
+///     .unwrap();
+/// assert_eq!(format!("{:x}", key), "3132333435363738393031323334353637383930");
+/// ```
+pub struct HexKey(Vec<u8>);
+
+impl HexKey {
+    pub(crate) fn new(key: Vec<u8>) -> Self {
+        HexKey(key)
+    }
+}
+
+impl fmt::LowerHex for HexKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0.iter() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::UpperHex for HexKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0.iter() {
+            write!(f, "{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for HexKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HexKey(***)")
+    }
+}
+
+impl Drop for HexKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
@@ -1,9 +1,11 @@
 use super::{
-    DEFAULT_OTP_HASH, DEFAULT_OTP_OUT_BASE, DEFAULT_OTP_OUT_LEN, DEFAULT_TOTP_PERIOD,
-    DEFAULT_TOTP_T0,
+    ErrorCode, HOTPBuilder, TOTPBuilder, DEFAULT_OTP_HASH, DEFAULT_OTP_OUT_BASE,
+    DEFAULT_OTP_OUT_LEN, DEFAULT_TOTP_PERIOD, DEFAULT_TOTP_T0,
 };
+use crate::oath::util::base32_encode;
 use crate::oath::HashFunction;
 use std::collections::HashMap;
+use std::str::FromStr;
 use url::Url;
 
 macro_rules! do_insert_param {
@@ -48,6 +50,28 @@ macro_rules! insert_param_opt_f {
     }};
 }
 
+macro_rules! set_common_params {
+    ($builder: ident, $params: ident) => {{
+        match $params.get("secret") {
+            Some(secret) => {
+                $builder.base32_key(secret);
+            }
+            None => {
+                return Err(ErrorCode::InvalidUri);
+            }
+        }
+        if let Some(algo) = $params.get("algorithm") {
+            $builder.hash_function(parse_param(algo)?);
+        }
+        if let Some(digits) = $params.get("digits") {
+            $builder.output_len(parse_param(digits)?);
+        }
+        if let Some(base) = $params.get("base") {
+            $builder.output_base(base);
+        }
+    }};
+}
+
 #[derive(Eq, PartialEq)]
 pub(crate) enum UriType {
     TOTP,
@@ -209,10 +233,7 @@ impl<'a> KeyUriBuilder<'a> {
         };
         uri.set_path(&label_final);
 
-        let secret_final = base32::encode(
-            base32::Alphabet::RFC4648 { padding: false },
-            self.key.as_slice(),
-        );
+        let secret_final = base32_encode(self.key.as_slice());
         uri.query_pairs_mut().append_pair("secret", &secret_final);
 
         insert_param!(self, uri, self.issuer, "issuer", "", true);
@@ -246,3 +267,154 @@ impl<'a> KeyUriBuilder<'a> {
         uri.into_string()
     }
 }
+
+/// Builder returned by [`parse_uri`], whose variant depends on the URI type.
+///
+/// [`parse_uri`]: fn.parse_uri.html
+pub enum OATHBuilder {
+    HOTP(HOTPBuilder),
+    TOTP(TOTPBuilder),
+}
+
+fn parse_param<T: FromStr>(value: &str) -> Result<T, ErrorCode> {
+    value.parse().map_err(|_| ErrorCode::InvalidUri)
+}
+
+/// Parses a Key Uri Format, as generated by `key_uri_format()`, and returns the builder matching
+/// its type with the `secret`, `algorithm`, `digits`, `base`, `counter`, `period` and `t0`
+/// parameters applied. Other parameters, as well as the label, are ignored.
+///
+/// A malformed URI, an unknown type, a missing `secret` (or `counter` for HOTP) or a parameter
+/// that cannot be parsed results in an `InvalidUri` error. Invalid values that can be parsed,
+/// such as a badly encoded secret, are reported by the builder's `finalize()` method.
+///
+/// ## Example
+///
+/// ```
+/// use libreauth::oath::{parse_uri, OATHBuilder};
+///
+/// let uri = "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1&digits=8";
+/// let code = match parse_uri(uri).unwrap() {
+///     OATHBuilder::HOTP(builder) => builder.finalize().unwrap().generate(),
+///     OATHBuilder::TOTP(builder) => builder.finalize().unwrap().generate(),
+/// };
+/// assert_eq!(code.len(), 8);
+/// ```
+pub fn parse_uri(uri: &str) -> Result<OATHBuilder, ErrorCode> {
+    let uri = Url::parse(uri).map_err(|_| ErrorCode::InvalidUri)?;
+    if uri.scheme() != "otpauth" {
+        return Err(ErrorCode::InvalidUri);
+    }
+    let params: HashMap<String, String> = uri.query_pairs().into_owned().collect();
+    match uri.host_str() {
+        Some("hotp") => {
+            let mut builder = HOTPBuilder::new();
+            set_common_params!(builder, params);
+            match params.get("counter") {
+                Some(counter) => {
+                    builder.counter(parse_param(counter)?);
+                }
+                None => {
+                    return Err(ErrorCode::InvalidUri);
+                }
+            }
+            Ok(OATHBuilder::HOTP(builder))
+        }
+        Some("totp") => {
+            let mut builder = TOTPBuilder::new();
+            set_common_params!(builder, params);
+            if let Some(period) = params.get("period") {
+                builder.period(parse_param(period)?);
+            }
+            if let Some(t0) = params.get("t0") {
+                builder.initial_time(parse_param(t0)?);
+            }
+            Ok(OATHBuilder::TOTP(builder))
+        }
+        _ => Err(ErrorCode::InvalidUri),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_uri, OATHBuilder};
+    use crate::hash::HashFunction;
+    use crate::oath::{ErrorCode, HOTPBuilder, ParametersVisibility, TOTPBuilder};
+
+    #[test]
+    fn test_parse_uri_hotp() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let hotp = HOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .counter(42)
+            .output_len(8)
+            .hash_function(HashFunction::Sha256)
+            .finalize()
+            .unwrap();
+        let uri = hotp
+            .key_uri_format("Provider 1", "alice@example.com")
+            .finalize();
+        match parse_uri(&uri).unwrap() {
+            OATHBuilder::HOTP(builder) => {
+                assert_eq!(builder.finalize().unwrap().generate(), hotp.generate());
+            }
+            OATHBuilder::TOTP(_) => panic!("HOTP URI parsed as TOTP"),
+        }
+    }
+
+    #[test]
+    fn test_parse_uri_totp() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(7)
+            .output_base("0123456789ab")
+            .period(60)
+            .initial_time(42)
+            .finalize()
+            .unwrap();
+        let uri = totp
+            .key_uri_format("Provider 1", "alice@example.com")
+            .parameters_visibility_policy(ParametersVisibility::ShowAll)
+            .finalize();
+        match parse_uri(&uri).unwrap() {
+            OATHBuilder::TOTP(builder) => {
+                assert_eq!(builder.finalize().unwrap().generate(), totp.generate());
+            }
+            OATHBuilder::HOTP(_) => panic!("TOTP URI parsed as HOTP"),
+        }
+    }
+
+    #[test]
+    fn test_parse_uri_invalid() {
+        let tests = [
+            "",
+            "not an uri",
+            "https://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            "otpauth://motp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            "otpauth://totp/Provider1:alice@example.com",
+            "otpauth://hotp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            "otpauth://hotp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&counter=-1",
+            "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=six",
+            "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&algorithm=MD5",
+        ];
+        for uri in tests.iter() {
+            match parse_uri(uri) {
+                Err(ErrorCode::InvalidUri) => {}
+                _ => panic!("{} has been parsed", uri),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_uri_invalid_secret() {
+        let uri = "otpauth://totp/Provider1:alice@example.com?secret=G1";
+        match parse_uri(uri).unwrap() {
+            OATHBuilder::TOTP(builder) => match builder.finalize() {
+                Err(ErrorCode::InvalidKey) => {}
+                _ => panic!("an invalid secret has been accepted"),
+            },
+            OATHBuilder::HOTP(_) => panic!("TOTP URI parsed as HOTP"),
+        }
+    }
+}
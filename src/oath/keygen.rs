@@ -0,0 +1,110 @@
+use super::util::base32_encode;
+use super::{ErrorCode, KEY_MIN_LEN};
+use getrandom::getrandom;
+use hex;
+
+/// Generates a random key of `len` bytes suitable for HOTP and TOTP.
+///
+/// The key is generated using the operating system's cryptographically secure random number
+/// source. Keys shorter than 16 bytes (128 bits, the minimum required by RFC 4226) are refused.
+///
+/// ## Examples
+/// ```
+/// let key = libreauth::oath::key_gen(20).unwrap();
+/// assert_eq!(key.len(), 20);
+///
+/// let code = libreauth::oath::HOTPBuilder::new()
+///     .key(&key)
+///     .finalize()
+///     .unwrap()
+///     .generate();
+/// assert_eq!(code.len(), 6);
+/// ```
+pub fn key_gen(len: usize) -> Result<Vec<u8>, ErrorCode> {
+    if len < KEY_MIN_LEN {
+        return Err(ErrorCode::InvalidKeyLen);
+    }
+    let mut key = vec![0; len];
+    match getrandom(&mut key) {
+        Ok(_) => Ok(key),
+        Err(_) => Err(ErrorCode::RandomError),
+    }
+}
+
+/// Generates a random key of `len` bytes and returns it as a base32 encoded string, ready to be
+/// used with `base32_key`.
+///
+/// ## Examples
+/// ```
+/// let key = libreauth::oath::key_gen_base32(20).unwrap();
+/// assert_eq!(key.len(), 32);
+///
+/// let totp = libreauth::oath::TOTPBuilder::new()
+///     .base32_key(&key)
+///     .finalize();
+/// assert!(totp.is_ok());
+/// ```
+pub fn key_gen_base32(len: usize) -> Result<String, ErrorCode> {
+    let key = key_gen(len)?;
+    Ok(base32_encode(&key))
+}
+
+/// Generates a random key of `len` bytes and returns it as an hexadecimal encoded string, ready to
+/// be used with `hex_key`.
+///
+/// ## Examples
+/// ```
+/// let key = libreauth::oath::key_gen_hex(20).unwrap();
+/// assert_eq!(key.len(), 40);
+///
+/// let hotp = libreauth::oath::HOTPBuilder::new()
+///     .hex_key(&key)
+///     .finalize();
+/// assert!(hotp.is_ok());
+/// ```
+pub fn key_gen_hex(len: usize) -> Result<String, ErrorCode> {
+    let key = key_gen(len)?;
+    Ok(hex::encode(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_gen, key_gen_base32, key_gen_hex};
+    use crate::oath::{ErrorCode, HOTPBuilder};
+
+    #[test]
+    fn test_key_gen_len() {
+        for len in [16, 20, 32, 64, 128].iter() {
+            assert_eq!(key_gen(*len).unwrap().len(), *len);
+        }
+    }
+
+    #[test]
+    fn test_key_gen_uniqueness() {
+        assert_ne!(key_gen(20).unwrap(), key_gen(20).unwrap());
+    }
+
+    #[test]
+    fn test_key_gen_too_short() {
+        for len in [0, 1, 10, 15].iter() {
+            match key_gen(*len) {
+                Err(ErrorCode::InvalidKeyLen) => {}
+                _ => panic!("a {} bytes key has been generated", len),
+            }
+        }
+        assert!(key_gen_base32(15).is_err());
+        assert!(key_gen_hex(15).is_err());
+    }
+
+    #[test]
+    fn test_key_gen_encoded() {
+        let key = key_gen_base32(20).unwrap();
+        let hotp_b32 = HOTPBuilder::new().base32_key(&key).finalize();
+        assert!(hotp_b32.is_ok());
+
+        let key = key_gen_hex(20).unwrap();
+        assert_eq!(key.len(), 40);
+        let hotp_hex = HOTPBuilder::new().hex_key(&key).finalize();
+        assert!(hotp_hex.is_ok());
+    }
+}
@@ -40,6 +40,13 @@ const DEFAULT_OTP_OUT_BASE: &str = "0123456789";
 const DEFAULT_OTP_OUT_LEN: usize = 6;
 const DEFAULT_TOTP_PERIOD: u32 = 30;
 const DEFAULT_TOTP_T0: u64 = 0;
+const KEY_MIN_LEN: usize = 16;
+const MAX_COUNTER_RANGE: u64 = 1_000;
+const MAX_SEQUENCE_LEN: usize = 10_000;
+
+/// The output base used by the builders unless another one is set, which makes the codes decimal
+/// numbers.
+pub const DEFAULT_OUTPUT_BASE: &[u8] = DEFAULT_OTP_OUT_BASE.as_bytes();
 
 /// Error codes used both in the rust and C interfaces.
 ///
@@ -83,6 +90,10 @@ const DEFAULT_TOTP_T0: u64 = 0;
 ///             <td>LIBREAUTH_OATH_CODE_TOO_BIG</td>
 ///         </tr>
 ///         <tr>
+///             <td>RangeTooLarge</td>
+///             <td>LIBREAUTH_OATH_RANGE_TOO_LARGE</td>
+///         </tr>
+///         <tr>
 ///             <td>InvalidKey</td>
 ///             <td>LIBREAUTH_OATH_INVALID_KEY</td>
 ///         </tr>
@@ -91,13 +102,53 @@ const DEFAULT_TOTP_T0: u64 = 0;
 ///             <td>LIBREAUTH_OATH_INVALID_PERIOD</td>
 ///         </tr>
 ///         <tr>
+///             <td>IncompatibleOption</td>
+///             <td>LIBREAUTH_OATH_INCOMPATIBLE_OPTION</td>
+///         </tr>
+///         <tr>
+///             <td>UnsupportedAlgorithm</td>
+///             <td>LIBREAUTH_OATH_UNSUPPORTED_ALGORITHM</td>
+///         </tr>
+///         <tr>
+///             <td>WeakKey</td>
+///             <td>LIBREAUTH_OATH_WEAK_KEY</td>
+///         </tr>
+///         <tr>
+///             <td>InvalidBase</td>
+///             <td>LIBREAUTH_OATH_INVALID_BASE</td>
+///         </tr>
+///         <tr>
 ///             <td>InvalidUTF8</td>
 ///             <td>LIBREAUTH_OATH_INVALID_UTF8</td>
 ///         </tr>
+///         <tr>
+///             <td>InvalidUri</td>
+///             <td>LIBREAUTH_OATH_INVALID_URI</td>
+///         </tr>
+///         <tr>
+///             <td>InvalidJson</td>
+///             <td>LIBREAUTH_OATH_INVALID_JSON</td>
+///         </tr>
+///         <tr>
+///             <td>RandomError</td>
+///             <td>LIBREAUTH_OATH_RANDOM_ERROR</td>
+///         </tr>
+///         <tr>
+///             <td>ClockError</td>
+///             <td>LIBREAUTH_OATH_CLOCK_ERROR</td>
+///         </tr>
+///         <tr>
+///             <td>TimestampOverflow</td>
+///             <td>LIBREAUTH_OATH_TIMESTAMP_OVERFLOW</td>
+///         </tr>
+///         <tr>
+///             <td>CounterOverflow</td>
+///             <td>LIBREAUTH_OATH_COUNTER_OVERFLOW</td>
+///         </tr>
 ///     </tbody>
 /// </table>
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum ErrorCode {
     Success = 0,
 
@@ -108,11 +159,216 @@ pub enum ErrorCode {
     InvalidKeyLen = 11,
     CodeTooSmall = 12,
     CodeTooBig = 13,
+    RangeTooLarge = 14,
 
     InvalidKey = 20,
     InvalidPeriod = 21,
+    IncompatibleOption = 22,
+    UnsupportedAlgorithm = 23,
+    WeakKey = 24,
+    InvalidBase = 25,
 
     InvalidUTF8 = 30,
+    InvalidUri = 31,
+    InvalidJson = 32,
+
+    RandomError = 40,
+    ClockError = 41,
+    TimestampOverflow = 42,
+    CounterOverflow = 43,
+}
+
+/// Error codes are ordered by their integer value, which is also the value exposed to C. Since the
+/// values are grouped by category (buffer errors, then output parameters, then key and
+/// configuration, encoding, and finally runtime errors), this order makes it possible to pick the
+/// most significant error of a set but has no meaning within a category.
+impl PartialOrd for ErrorCode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ErrorCode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (*self as i32).cmp(&(*other as i32))
+    }
+}
+
+impl From<crate::hash::HashFunctionError> for ErrorCode {
+    fn from(_error: crate::hash::HashFunctionError) -> Self {
+        ErrorCode::UnsupportedAlgorithm
+    }
+}
+
+impl From<std::time::SystemTimeError> for ErrorCode {
+    fn from(_error: std::time::SystemTimeError) -> Self {
+        ErrorCode::ClockError
+    }
+}
+
+/// A list of errors, used to report every issue found while validating a configuration instead of
+/// only the first one.
+///
+/// Errors can be accumulated using the `|` operator, duplicates being ignored.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::{ErrorCode, ValidationErrors};
+///
+/// let mut errors = ValidationErrors::new();
+/// assert!(errors.is_empty());
+///
+/// errors = errors | ErrorCode::InvalidKeyLen | ErrorCode::InvalidPeriod;
+/// errors |= ErrorCode::InvalidKeyLen;
+/// assert_eq!(
+///     errors.as_slice(),
+///     &[ErrorCode::InvalidKeyLen, ErrorCode::InvalidPeriod]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationErrors(Vec<ErrorCode>);
+
+impl ValidationErrors {
+    /// Creates an empty list of errors.
+    pub fn new() -> Self {
+        ValidationErrors(Vec::new())
+    }
+
+    /// Returns `true` if no error has been added.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the errors, in the order they have been added.
+    pub fn as_slice(&self) -> &[ErrorCode] {
+        &self.0
+    }
+
+    fn push(&mut self, error: ErrorCode) {
+        if !self.0.contains(&error) {
+            self.0.push(error);
+        }
+    }
+}
+
+impl From<ErrorCode> for ValidationErrors {
+    fn from(error: ErrorCode) -> Self {
+        ValidationErrors(vec![error])
+    }
+}
+
+impl From<ValidationErrors> for Vec<ErrorCode> {
+    fn from(errors: ValidationErrors) -> Self {
+        errors.0
+    }
+}
+
+impl std::ops::BitOr<ErrorCode> for ValidationErrors {
+    type Output = ValidationErrors;
+
+    fn bitor(mut self, error: ErrorCode) -> Self::Output {
+        self.push(error);
+        self
+    }
+}
+
+impl std::ops::BitOr for ValidationErrors {
+    type Output = ValidationErrors;
+
+    fn bitor(mut self, errors: ValidationErrors) -> Self::Output {
+        for error in errors.0 {
+            self.push(error);
+        }
+        self
+    }
+}
+
+impl std::ops::BitOrAssign<ErrorCode> for ValidationErrors {
+    fn bitor_assign(&mut self, error: ErrorCode) {
+        self.push(error);
+    }
+}
+
+/// Encodings that can be used to pass the shared secret as a string.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libreauth::oath::{Encoding, HOTPBuilder};
+///
+/// let code = HOTPBuilder::new()
+///     .set_key_encoded("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ", Encoding::Base32)
+///     .finalize()
+///     .unwrap()
+///     .generate();
+/// assert_eq!(code, "755224");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// The secret is used as is.
+    Ascii,
+    /// The secret is an hexadecimal encoded string.
+    Hex,
+    /// The secret is a base32 encoded string (RFC 4648).
+    Base32,
+    /// The secret is a base64 encoded string (RFC 4648).
+    Base64,
+    /// The secret is a base64url encoded string without padding (RFC 4648 section 5), such as
+    /// the `k` parameter of a JSON Web Key.
+    Base64Url,
+}
+
+// Guesses the encoding of a key for `auto_key`. Since the hexadecimal and base32 alphabets
+// overlap, a key valid in both is considered as base32 encoded.
+fn detect_key_encoding(key: &str) -> Encoding {
+    if key.is_empty() {
+        Encoding::Ascii
+    } else if crate::oath::util::base32_decode(key).is_ok() {
+        Encoding::Base32
+    } else if crate::oath::util::ct_hex_decode(key).is_ok() {
+        Encoding::Hex
+    } else {
+        Encoding::Ascii
+    }
+}
+
+// Emits a debug event through the `log` crate when the `logging` feature is enabled. Neither the
+// key nor the codes must ever be part of an event.
+macro_rules! log_debug {
+    ($($arg: tt)+) => {
+        #[cfg(feature = "logging")]
+        log::debug!($($arg)+);
+    };
+}
+
+// Emits a warning event through the `log` crate when the `logging` feature is enabled.
+macro_rules! log_warn {
+    ($($arg: tt)+) => {
+        #[cfg(feature = "logging")]
+        log::warn!($($arg)+);
+    };
+}
+
+// Increments a counter through the `metrics` crate when the `metrics` feature is enabled.
+macro_rules! count_event {
+    ($name: expr) => {
+        #[cfg(feature = "metrics")]
+        metrics::counter!($name).increment(1);
+    };
+}
+
+// Increments either the verification success or failure counter of the given OTP type.
+macro_rules! count_verification {
+    ($otp: literal, $is_valid: expr) => {
+        #[cfg(feature = "metrics")]
+        {
+            if $is_valid {
+                count_event!(concat!("libreauth.", $otp, ".verify_success_count"));
+            } else {
+                count_event!(concat!("libreauth.", $otp, ".verify_failure_count"));
+            }
+        }
+    };
 }
 
 macro_rules! builder_common {
@@ -125,37 +381,138 @@ macro_rules! builder_common {
 
         /// Sets the shared secret. This secret is passed as an ASCII string.
         pub fn ascii_key(&mut self, key: &str) -> &mut $t {
-            self.key = Some(key.as_bytes().to_vec());
-            self
+            self.set_key_encoded(key, Encoding::Ascii)
         }
 
         /// Sets the shared secret. This secret is passed as an hexadecimal encoded string.
         pub fn hex_key(&mut self, key: &str) -> &mut $t {
-            match hex::decode(key) {
-                Ok(k) => { self.key = Some(k); }
-                Err(_) => { self.runtime_error = Some(ErrorCode::InvalidKey); }
-            }
-            self
+            self.set_key_encoded(key, Encoding::Hex)
         }
 
-        /// Sets the shared secret. This secret is passed as a base32 encoded string.
+        /// Sets the shared secret. This secret is passed as a base32 encoded string, with or
+        /// without padding.
         pub fn base32_key(&mut self, key: &str) -> &mut $t {
-            match base32::decode(base32::Alphabet::RFC4648 { padding: false }, &key) {
-                Some(k) => { self.key = Some(k); }
-                None => { self.runtime_error = Some(ErrorCode::InvalidKey); }
-            }
-            self
+            self.set_key_encoded(key, Encoding::Base32)
+        }
+
+        /// Sets the shared secret. This secret is passed as a base32 encoded string with `=`
+        /// padding, as exported by many provisioning systems. Since the padding is optional,
+        /// unpadded strings are also accepted: this is the same as
+        /// [base32_key](#method.base32_key).
+        pub fn base32_key_padded(&mut self, key: &str) -> &mut $t {
+            self.base32_key(key)
         }
 
         /// Sets the shared secret. This secret is passed as a base64 encoded string.
         pub fn base64_key(&mut self, key: &str) -> &mut $t {
-            match base64::decode(key) {
-                Ok(k) => { self.key = Some(k); }
-                Err(_) => { self.runtime_error = Some(ErrorCode::InvalidKey); }
+            self.set_key_encoded(key, Encoding::Base64)
+        }
+
+        /// Sets the shared secret. This secret is passed as a base64url encoded string without
+        /// padding, which is how JSON Web Keys (RFC 7517) store symmetric keys.
+        pub fn base64url_key(&mut self, key: &str) -> &mut $t {
+            self.set_key_encoded(key, Encoding::Base64Url)
+        }
+
+        /// Sets the shared secret, guessing how it is encoded: a string only made of base32
+        /// characters (with optional `=` padding) is decoded as base32, else an even length string
+        /// only made of hexadecimal characters is decoded as hexadecimal, else the string is
+        /// used as an ASCII key. Since those alphabets overlap, a key valid in both base32 and
+        /// hexadecimal, such as `DEADBEEF`, is decoded as base32.
+        ///
+        /// This guess may be wrong: it is meant for prototyping and the explicit methods, such as
+        /// [base32_key](#method.base32_key), should be used in production code.
+        ///
+        /// ## Examples
+        /// ```
+        /// let code = libreauth::oath::HOTPBuilder::new()
+        ///     .auto_key("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")
+        ///     .finalize()
+        ///     .unwrap()
+        ///     .generate();
+        /// assert_eq!(code, "755224");
+        /// ```
+        pub fn auto_key(&mut self, key: &str) -> &mut $t {
+            self.set_key_encoded(key, crate::oath::detect_key_encoding(key))
+        }
+
+        /// Sets the shared secret. This secret is passed as a string using the given [encoding](enum.Encoding.html).
+        pub fn set_key_encoded(&mut self, key: &str, encoding: Encoding) -> &mut $t {
+            let decoded = match encoding {
+                Encoding::Ascii => Some(key.as_bytes().to_vec()),
+                Encoding::Hex => crate::oath::util::ct_hex_decode(key).ok(),
+                Encoding::Base32 => crate::oath::util::base32_decode(key).ok(),
+                Encoding::Base64 => base64::decode(key).ok(),
+                Encoding::Base64Url => base64::decode_config(key, base64::URL_SAFE_NO_PAD).ok(),
+            };
+            match decoded {
+                Some(k) => {
+                    self.key = Some(k);
+                }
+                None => {
+                    self.runtime_error = Some(ErrorCode::InvalidKey);
+                }
             }
             self
         }
 
+        /// Returns the length, in bytes, of the shared secret or `None` if no secret has been
+        /// set. Encoded secrets are measured once decoded.
+        ///
+        /// ## Examples
+        /// ```
+        /// let mut builder = libreauth::oath::HOTPBuilder::new();
+        /// assert_eq!(builder.key_len(), None);
+        ///
+        /// builder.base32_key("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        /// assert_eq!(builder.key_len(), Some(20));
+        /// ```
+        pub fn key_len(&self) -> Option<usize> {
+            self.key.as_ref().map(Vec::len)
+        }
+
+        /// Checks the quality of the shared secret and returns every issue found:
+        /// `ErrorCode::InvalidKey` if no valid key has been set, `ErrorCode::InvalidKeyLen` if
+        /// the key is shorter than the 16 bytes required by RFC 4226 (or the minimum set using
+        /// `HOTPBuilder::minimum_key_len`) and `ErrorCode::WeakKey` if the key only contains null
+        /// bytes or repeats a pattern no longer than a third of the key, rounded up.
+        ///
+        /// `finalize` does not perform those checks, so applications can use this function to
+        /// enforce a policy on the keys they store.
+        ///
+        /// ## Examples
+        /// ```
+        /// use libreauth::oath::{ErrorCode, HOTPBuilder};
+        ///
+        /// let mut builder = HOTPBuilder::new();
+        /// builder.ascii_key("12345678901234567890");
+        /// assert!(builder.validate_key().is_ok());
+        ///
+        /// builder.ascii_key("abcabcab");
+        /// assert_eq!(
+        ///     builder.validate_key(),
+        ///     Err(vec![ErrorCode::InvalidKeyLen, ErrorCode::WeakKey])
+        /// );
+        /// ```
+        pub fn validate_key(&self) -> Result<(), Vec<ErrorCode>> {
+            let key = match (self.runtime_error, &self.key) {
+                (None, Some(key)) => key,
+                _ => return Err(vec![ErrorCode::InvalidKey]),
+            };
+            let mut errors = crate::oath::ValidationErrors::new();
+            if key.len() < self.key_min_len() {
+                errors |= ErrorCode::InvalidKeyLen;
+            }
+            if crate::oath::util::is_weak_key(key) {
+                errors |= ErrorCode::WeakKey;
+            }
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.into())
+            }
+        }
+
         fn code_length(&self) -> usize {
             let base_len = self.output_base.len();
             let mut nb_bits = base_len;
@@ -174,37 +531,66 @@ macro_rules! builder_common {
             self
         }
 
-        /// Sets the base used to represents the output code. Default is "0123456789".
+        /// Sets the base used to represents the output code. Default is
+        /// [DEFAULT_OUTPUT_BASE](constant.DEFAULT_OUTPUT_BASE.html), "0123456789".
+        ///
+        /// The base is checked by `finalize` using
+        /// [util::validate_output_base](util/fn.validate_output_base.html), which returns the first
+        /// error found.
         pub fn output_base(&mut self, base: &str) -> &mut $t {
             self.output_base = base.to_string();
             self
         }
 
+        fn check_output_base(&self) -> Result<(), ErrorCode> {
+            crate::oath::util::validate_output_base(self.output_base.as_bytes())
+                .map_err(|errors| errors.as_slice()[0])
+        }
+
         /// Sets the hash function. Default is Sha1.
         pub fn hash_function(&mut self, hash_function: HashFunction) -> &mut $t {
             self.hash_function = hash_function;
             self
         }
-    }
+    };
 }
 
 #[cfg(feature = "oath-uri")]
 mod key_uri;
 #[cfg(feature = "oath-uri")]
-pub use self::key_uri::{KeyUriBuilder, ParametersVisibility};
+pub use self::key_uri::{parse_uri, KeyUriBuilder, OATHBuilder, ParametersVisibility};
+
+pub mod util;
+
+mod keygen;
+pub use self::keygen::{key_gen, key_gen_base32, key_gen_hex};
+
+mod key_material;
+pub use self::key_material::{HexKey, KeyMaterial};
 
 mod hotp;
 pub use self::hotp::HOTPBuilder;
+pub use self::hotp::HOTPConfig;
+#[cfg(feature = "debug")]
+pub use self::hotp::HOTPDebugInfo;
 pub use self::hotp::HOTP;
 
 mod totp;
 pub use self::totp::TOTPBuilder;
+#[cfg(feature = "debug")]
+pub use self::totp::TOTPDebugInfo;
 pub use self::totp::TOTP;
 
 #[cfg(feature = "cbindings")]
 mod cbindings;
 #[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_hotp_counter_set;
+#[cfg(feature = "cbindings")]
 pub use self::cbindings::libreauth_hotp_generate;
+#[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_hotp_generate_n;
+#[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_hotp_generate_with_len;
 #[cfg(all(feature = "cbindings", feature = "oath-uri"))]
 pub use self::cbindings::libreauth_hotp_get_uri;
 #[cfg(feature = "cbindings")]
@@ -212,10 +598,20 @@ pub use self::cbindings::libreauth_hotp_init;
 #[cfg(feature = "cbindings")]
 pub use self::cbindings::libreauth_hotp_is_valid;
 #[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_hotp_is_valid_at_counter;
+#[cfg(all(feature = "cbindings", feature = "oath-uri"))]
+pub use self::cbindings::libreauth_hotp_to_uri;
+#[cfg(feature = "cbindings")]
 pub use self::cbindings::HOTPcfg;
 
+#[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_free_codes;
 #[cfg(feature = "cbindings")]
 pub use self::cbindings::libreauth_totp_generate;
+#[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_totp_generate_for_window;
+#[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_totp_generate_with_len;
 #[cfg(all(feature = "cbindings", feature = "oath-uri"))]
 pub use self::cbindings::libreauth_totp_get_uri;
 #[cfg(feature = "cbindings")]
@@ -223,4 +619,8 @@ pub use self::cbindings::libreauth_totp_init;
 #[cfg(feature = "cbindings")]
 pub use self::cbindings::libreauth_totp_is_valid;
 #[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_totp_is_valid_at;
+#[cfg(all(feature = "cbindings", feature = "oath-uri"))]
+pub use self::cbindings::libreauth_totp_to_uri;
+#[cfg(feature = "cbindings")]
 pub use self::cbindings::TOTPcfg;
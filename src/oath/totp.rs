@@ -1,43 +1,300 @@
 #[cfg(feature = "oath-uri")]
 use super::DEFAULT_KEY_URI_PARAM_POLICY;
 use super::{
-    ErrorCode, HOTPBuilder, HashFunction, DEFAULT_OTP_HASH, DEFAULT_OTP_OUT_BASE,
-    DEFAULT_OTP_OUT_LEN, DEFAULT_TOTP_PERIOD, DEFAULT_TOTP_T0,
+    Encoding, ErrorCode, HOTPBuilder, HashFunction, DEFAULT_OTP_HASH, DEFAULT_OTP_OUT_BASE,
+    DEFAULT_OTP_OUT_LEN, DEFAULT_TOTP_PERIOD, DEFAULT_TOTP_T0, HOTP, KEY_MIN_LEN,
 };
 #[cfg(feature = "oath-uri")]
 use crate::oath::key_uri::{KeyUriBuilder, UriType};
-use base32;
+#[cfg(feature = "serde")]
+use crate::oath::util::base32_decode;
+#[cfg(any(feature = "json", feature = "serde"))]
+use crate::oath::util::base32_encode;
+#[cfg(feature = "oath-uri")]
+use crate::oath::ParametersVisibility;
 use base64;
-use hex;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 #[cfg(feature = "oath-uri")]
 use std::collections::HashMap;
-use std::time::SystemTime;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeLess};
+
+// Returned by `TOTPBuilder::time_based_counter` when the counter cannot be computed.
+const INVALID_COUNTER: u64 = 0xffff_ffff_ffff_ffff;
+
+/// Authenticator applications whose limitations the configuration must comply with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub(crate) enum Compatibility {
+    Default,
+    GoogleAuthenticator,
+    MicrosoftAuthenticator,
+}
+
+/// Converts a duration since the Unix epoch into a timestamp in seconds or milliseconds.
+fn to_time_unit(duration: Duration, milliseconds: bool) -> i64 {
+    if milliseconds {
+        duration.as_millis() as i64
+    } else {
+        duration.as_secs() as i64
+    }
+}
+
+/// The intermediate values of a TOTP code computation, as returned by
+/// [`TOTP::debug_info`](struct.TOTP.html#method.debug_info).
+#[cfg(feature = "debug")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TOTPDebugInfo {
+    /// The counter, i.e. the number of periods since the initial time.
+    pub counter: u64,
+    /// The hex encoded HMAC of the counter.
+    pub hmac: String,
+    /// The offset of the four bytes extracted by the dynamic truncation.
+    pub offset: usize,
+    /// The 31 bits integer extracted by the dynamic truncation, before the modulo is applied.
+    pub truncated_value: u32,
+    /// The resulting code.
+    pub code: String,
+}
 
 /// Generates and checks TOTP codes.
+#[derive(Clone)]
 pub struct TOTP {
     key: Vec<u8>,
+    // The timestamp offset, the epoch offset and the period are expressed in milliseconds if
+    // `milliseconds` is set and in seconds otherwise. The initial time is always expressed in
+    // seconds.
     timestamp_offset: i64,
+    epoch_offset: i64,
     positive_tolerance: u64,
     negative_tolerance: u64,
-    period: u32,
+    period: u64,
     initial_time: u64,
+    milliseconds: bool,
     output_len: usize,
     output_base: String,
     hash_function: HashFunction,
+    compatibility: Compatibility,
+    #[cfg(feature = "oath-uri")]
+    image_url: Option<String>,
+    account_identifier: Option<String>,
+    issuer: Option<String>,
+    // Replaces the system clock in tests. It is expressed in milliseconds if `milliseconds` is set
+    // and in seconds otherwise.
+    #[cfg(test)]
+    fixed_now: Option<i64>,
+}
+
+impl fmt::Debug for TOTP {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut d = f.debug_struct("TOTP");
+        d.field("key", &format_args!("***"))
+            .field("timestamp_offset", &self.timestamp_offset)
+            .field("epoch_offset", &self.epoch_offset)
+            .field("positive_tolerance", &self.positive_tolerance)
+            .field("negative_tolerance", &self.negative_tolerance)
+            .field("period", &self.period)
+            .field("initial_time", &self.initial_time)
+            .field("milliseconds", &self.milliseconds)
+            .field("output_len", &self.output_len)
+            .field("output_base", &self.output_base)
+            .field("hash_function", &format_args!("{}", self.hash_function))
+            .field("compatibility", &self.compatibility);
+        #[cfg(feature = "oath-uri")]
+        d.field("image_url", &self.image_url);
+        #[cfg(feature = "pii-logging")]
+        d.field("account_identifier", &self.account_identifier);
+        #[cfg(not(feature = "pii-logging"))]
+        d.field(
+            "account_identifier",
+            &self
+                .account_identifier
+                .as_ref()
+                .map(|_| format_args!("***")),
+        );
+        d.field("issuer", &self.issuer);
+        d.finish()
+    }
+}
+
+/// The serialized form of a TOTP, the key being base32 encoded.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedTOTP {
+    key: String,
+    timestamp_offset: i64,
+    epoch_offset: i64,
+    positive_tolerance: u64,
+    negative_tolerance: u64,
+    period: u64,
+    initial_time: u64,
+    milliseconds: bool,
+    output_len: usize,
+    output_base: String,
+    hash_function: String,
+    compatibility: Compatibility,
+    #[cfg(feature = "oath-uri")]
+    #[serde(default)]
+    image_url: Option<String>,
+    #[serde(default)]
+    account_identifier: Option<String>,
+    #[serde(default)]
+    issuer: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for TOTP {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedTOTP {
+            key: base32_encode(&self.key),
+            timestamp_offset: self.timestamp_offset,
+            epoch_offset: self.epoch_offset,
+            positive_tolerance: self.positive_tolerance,
+            negative_tolerance: self.negative_tolerance,
+            period: self.period,
+            initial_time: self.initial_time,
+            milliseconds: self.milliseconds,
+            output_len: self.output_len,
+            output_base: self.output_base.clone(),
+            hash_function: self.hash_function.to_string(),
+            compatibility: self.compatibility,
+            #[cfg(feature = "oath-uri")]
+            image_url: self.image_url.clone(),
+            account_identifier: self.account_identifier.clone(),
+            issuer: self.issuer.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The deserialized values are checked the same way `TOTPBuilder::finalize` does.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TOTP {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = SerializedTOTP::deserialize(deserializer)?;
+        let key = base32_decode(&s.key)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Str("***"), &"a base32 key"))?;
+        let hash_function: HashFunction = s.hash_function.parse().map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&s.hash_function), &"a hash function")
+        })?;
+        if s.period == 0 {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(0),
+                &"a non-zero period",
+            ));
+        }
+        let totp = TOTP {
+            key,
+            timestamp_offset: s.timestamp_offset,
+            epoch_offset: s.epoch_offset,
+            positive_tolerance: s.positive_tolerance,
+            negative_tolerance: s.negative_tolerance,
+            period: s.period,
+            initial_time: s.initial_time,
+            milliseconds: s.milliseconds,
+            output_len: s.output_len,
+            output_base: s.output_base,
+            hash_function,
+            compatibility: s.compatibility,
+            #[cfg(feature = "oath-uri")]
+            image_url: s.image_url,
+            account_identifier: s.account_identifier,
+            issuer: s.issuer,
+            #[cfg(test)]
+            fixed_now: None,
+        };
+        if let Err(e) = totp.get_hotp(0) {
+            return Err(de::Error::custom(format!("invalid TOTP: {:?}", e)));
+        }
+        if !totp.is_compatible() {
+            return Err(de::Error::custom(format!(
+                "invalid TOTP: {:?}",
+                ErrorCode::IncompatibleOption
+            )));
+        }
+        Ok(totp)
+    }
 }
 
 impl TOTP {
-    fn get_counter(&self) -> u64 {
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-        let timestamp = timestamp + self.timestamp_offset;
-        let timestamp = timestamp as u64;
-        if timestamp < self.initial_time {
-            panic!("The current Unix time is below the initial time.");
+    /// Returns the account identifier set using
+    /// [TOTPBuilder::account_identifier](struct.TOTPBuilder.html#method.account_identifier).
+    pub fn account_identifier(&self) -> Option<&str> {
+        self.account_identifier.as_deref()
+    }
+
+    /// Returns the issuer set using
+    /// [TOTPBuilder::issuer](struct.TOTPBuilder.html#method.issuer).
+    pub fn issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    /// Returns the account identifier, if any, formatted to be appended to log messages. It is
+    /// always empty unless the `pii-logging` feature is enabled.
+    #[cfg(feature = "logging")]
+    fn log_account(&self) -> String {
+        #[cfg(feature = "pii-logging")]
+        {
+            if let Some(ref id) = self.account_identifier {
+                return format!(" (account {})", id);
+            }
+        }
+        String::new()
+    }
+
+    fn get_timestamp(&self) -> Result<i64, ErrorCode> {
+        #[cfg(test)]
+        {
+            if let Some(now) = self.fixed_now {
+                return Ok(now + self.timestamp_offset);
+            }
+        }
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+        Ok(to_time_unit(now, self.milliseconds) + self.timestamp_offset)
+    }
+
+    fn get_initial_time(&self) -> u64 {
+        if self.milliseconds {
+            self.initial_time.saturating_mul(1000)
+        } else {
+            self.initial_time
+        }
+    }
+
+    #[cfg(feature = "oath-uri")]
+    fn get_period_secs(&self) -> u32 {
+        let period = if self.milliseconds {
+            self.period / 1000
+        } else {
+            self.period
+        };
+        period.min(0xffff_ffff) as u32
+    }
+
+    /// Returns the time elapsed since the initial time.
+    fn get_elapsed(&self, timestamp: i64) -> Result<u64, ErrorCode> {
+        let timestamp = timestamp
+            .checked_add(self.epoch_offset)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        if timestamp < 0 || (timestamp as u64) < self.get_initial_time() {
+            return Err(ErrorCode::TimestampOverflow);
         }
-        (timestamp - self.initial_time) / u64::from(self.period)
+        Ok(timestamp as u64 - self.get_initial_time())
+    }
+
+    fn get_counter_at(&self, timestamp: i64) -> Result<u64, ErrorCode> {
+        Ok(self.get_elapsed(timestamp)? / self.period)
+    }
+
+    fn get_counter(&self) -> Result<u64, ErrorCode> {
+        self.get_counter_at(self.get_timestamp()?)
     }
 
     /// Generate the current TOTP value.
@@ -53,8 +310,37 @@ impl TOTP {
     /// let code = totp.generate();
     /// assert_eq!(code.len(), 6);
     /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the system clock is set before the Unix epoch or if the current Unix time is
+    /// below the initial time.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(
+                hash_function = %self.hash_function,
+                output_len = self.output_len,
+                counter = tracing::field::Empty
+            )
+        )
+    )]
     pub fn generate(&self) -> String {
-        let counter = self.get_counter();
+        let counter = match self.get_counter() {
+            Ok(c) => c,
+            Err(ErrorCode::ClockError) => panic!("The system clock is set before the Unix epoch."),
+            Err(_) => panic!("The current Unix time is below the initial time."),
+        };
+        log_debug!(
+            "TOTP code generated for counter {}{}",
+            counter,
+            self.log_account()
+        );
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("counter", counter);
+        count_event!("libreauth.totp.generate_count");
         let hotp = HOTPBuilder::new()
             .key(&self.key.clone())
             .counter(counter)
@@ -63,11 +349,122 @@ impl TOTP {
             .hash_function(self.hash_function)
             .finalize();
         match hotp {
-            Ok(h) => h.generate(),
+            Ok(h) => h.compute_at(counter),
             Err(e) => panic!(e),
         }
     }
 
+    /// Generate the TOTP value for the given Unix time instead of the current one. If the
+    /// timestamp is below the initial time, `ErrorCode::TimestampOverflow` is returned. If the
+    /// millisecond mode is enabled, the timestamp is expressed in milliseconds.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate_at(1111111109).unwrap(), "07081804");
+    /// ```
+    pub fn generate_at(&self, timestamp: i64) -> Result<String, ErrorCode> {
+        let counter = self.get_counter_at(timestamp)?;
+        log_debug!(
+            "TOTP code generated for counter {}{}",
+            counter,
+            self.log_account()
+        );
+        count_event!("libreauth.totp.generate_count");
+        Ok(self.get_hotp(counter)?.compute_at(counter))
+    }
+
+    /// Generate the TOTP value of the previous period.
+    ///
+    /// This is useful to diagnose clock skews. If the previous period starts before the initial
+    /// time, `ErrorCode::TimestampOverflow` is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111120)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate_previous().unwrap(), "07081804");
+    /// ```
+    pub fn generate_previous(&self) -> Result<String, ErrorCode> {
+        let counter = self
+            .get_counter()?
+            .checked_sub(1)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        count_event!("libreauth.totp.generate_count");
+        Ok(self.get_hotp(counter)?.compute_at(counter))
+    }
+
+    /// Generate the TOTP value of the next period.
+    ///
+    /// This is useful to diagnose clock skews.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111060)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate_next().unwrap(), "07081804");
+    /// ```
+    pub fn generate_next(&self) -> Result<String, ErrorCode> {
+        let counter = self
+            .get_counter()?
+            .checked_add(1)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        count_event!("libreauth.totp.generate_count");
+        Ok(self.get_hotp(counter)?.compute_at(counter))
+    }
+
+    /// Generate the TOTP values of the `skew` periods before the current one, the current one and
+    /// the `skew` periods after it, from the oldest to the newest.
+    ///
+    /// If the first period starts before the initial time, `ErrorCode::TimestampOverflow` is
+    /// returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111090)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let codes = totp.window_codes(1).unwrap();
+    /// assert_eq!(codes.len(), 3);
+    /// assert_eq!(codes[1], "07081804");
+    /// assert_eq!(codes[2], "14050471");
+    /// ```
+    pub fn window_codes(&self, skew: u8) -> Result<Vec<String>, ErrorCode> {
+        let counter = self.get_counter()?;
+        let first = counter
+            .checked_sub(u64::from(skew))
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        let last = counter
+            .checked_add(u64::from(skew))
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        let hotp = self.get_hotp(counter)?;
+        count_event!("libreauth.totp.generate_count");
+        Ok((first..=last).map(|c| hotp.compute_at(c)).collect())
+    }
+
     /// Checks if the given code is valid. This implementation uses the [double HMAC verification](https://www.nccgroup.trust/us/about-us/newsroom-and-events/blog/2011/february/double-hmac-verification/) in order to prevent a timing side channel attack.
     ///
     /// ## Examples
@@ -81,90 +478,642 @@ impl TOTP {
     ///     .is_valid(&user_code);
     /// ```
     pub fn is_valid(&self, code: &str) -> bool {
-        let base_counter = self.get_counter();
-        for counter in
-            (base_counter - self.negative_tolerance)..=(base_counter + self.positive_tolerance)
+        match self.get_timestamp() {
+            Ok(timestamp) => self.is_valid_at(code, timestamp),
+            Err(_) => false,
+        }
+    }
+
+    /// Same as [is_valid](#method.is_valid).
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(totp.verify(&totp.generate()));
+    /// ```
+    pub fn verify(&self, code: &str) -> bool {
+        self.is_valid(code)
+    }
+
+    /// Checks if the given code is valid for the given Unix time instead of the current one,
+    /// taking the tolerance into account. If the millisecond mode is enabled, the timestamp is
+    /// expressed in milliseconds.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(totp.is_valid_at("07081804", 1111111109));
+    /// assert!(!totp.is_valid_at("07081804", 1234567890));
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, code),
+            fields(
+                hash_function = %self.hash_function,
+                output_len = self.output_len,
+                counter = tracing::field::Empty
+            )
+        )
+    )]
+    pub fn is_valid_at(&self, code: &str, timestamp: i64) -> bool {
+        let base_counter = match self.get_counter_at(timestamp) {
+            Ok(c) => c,
+            Err(_e) => {
+                log_debug!("TOTP verification failed: {:?}{}", _e, self.log_account());
+                count_verification!("totp", false);
+                return false;
+            }
+        };
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("counter", base_counter);
+        for counter in base_counter.saturating_sub(self.negative_tolerance)
+            ..=base_counter.saturating_add(self.positive_tolerance)
         {
-            let hotp = HOTPBuilder::new()
-                .key(&self.key.clone())
-                .counter(counter)
-                .output_len(self.output_len)
-                .hash_function(self.hash_function)
-                .finalize();
-            let is_valid = match hotp {
-                Ok(h) => h.is_valid(code),
-                Err(e) => panic!(e),
+            let is_valid = match self.get_hotp(counter) {
+                Ok(h) => h.check_at(code, counter),
+                Err(_) => false,
             };
             if is_valid {
+                log_debug!(
+                    "TOTP verification succeeded for counter {}{}",
+                    counter,
+                    self.log_account()
+                );
+                count_verification!("totp", true);
                 return true;
             }
         }
+        log_debug!(
+            "TOTP verification failed for counter {}{}",
+            base_counter,
+            self.log_account()
+        );
+        count_verification!("totp", false);
         false
     }
 
-    /// Creates the Key Uri Format according to the [Google authenticator
-    /// specification](https://github.com/google/google-authenticator/wiki/Key-Uri-Format).
-    /// This value can be used to generete QR codes which allow easy scanning by the end user.
-    /// The returned [`KeyUriBuilder`] allows for additional customizations.
+    #[cfg(feature = "chrono")]
+    fn get_datetime_timestamp(&self, dt: &chrono::DateTime<chrono::Utc>) -> i64 {
+        if self.milliseconds {
+            dt.timestamp_millis()
+        } else {
+            dt.timestamp()
+        }
+    }
+
+    /// Same as [generate_at](#method.generate_at), the time being given as a chrono `DateTime`.
     ///
-    /// **WARNING**: The finalized value contains the secret key of the authentication process and
-    /// should only be displayed to the corresponding user!
+    /// ## Examples
+    /// ```
+    /// use chrono::TimeZone;
     ///
-    /// ## Example
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let dt = chrono::Utc.with_ymd_and_hms(2005, 3, 18, 1, 58, 29).unwrap();
+    /// assert_eq!(totp.generate_at_datetime(dt).unwrap(), "07081804");
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn generate_at_datetime(
+        &self,
+        dt: chrono::DateTime<chrono::Utc>,
+    ) -> Result<String, ErrorCode> {
+        self.generate_at(self.get_datetime_timestamp(&dt))
+    }
+
+    /// Same as [is_valid_at](#method.is_valid_at), the time being given as a chrono `DateTime`.
+    /// If the date is before the initial time, `ErrorCode::TimestampOverflow` is returned.
     ///
+    /// ## Examples
     /// ```
+    /// use chrono::TimeZone;
+    ///
     /// let key_ascii = "12345678901234567890".to_owned();
-    /// let mut totp = libreauth::oath::TOTPBuilder::new()
+    /// let totp = libreauth::oath::TOTPBuilder::new()
     ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
     ///     .finalize()
     ///     .unwrap();
     ///
-    /// let uri = totp
-    ///     .key_uri_format("Provider1", "alice@example.com")
-    ///     .finalize();
+    /// let dt = chrono::Utc.with_ymd_and_hms(2005, 3, 18, 1, 58, 29).unwrap();
+    /// assert!(totp.verify_at_datetime("07081804", dt).unwrap());
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn verify_at_datetime(
+        &self,
+        code: &str,
+        dt: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, ErrorCode> {
+        let timestamp = self.get_datetime_timestamp(&dt);
+        self.get_counter_at(timestamp)?;
+        Ok(self.is_valid_at(code, timestamp))
+    }
+
+    /// Same as [generate_at](#method.generate_at), the time being given as a `SystemTime`. If that
+    /// time is before the Unix epoch, `ErrorCode::ClockError` is returned.
     ///
-    /// assert_eq!(
-    ///     uri,
-    ///     "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1"
-    /// );
+    /// ## Examples
     /// ```
-    #[cfg(feature = "oath-uri")]
-    pub fn key_uri_format<'a>(
-        &'a self,
-        issuer: &'a str,
-        account_name: &'a str,
-    ) -> KeyUriBuilder<'a> {
-        KeyUriBuilder {
-            parameters_visibility: DEFAULT_KEY_URI_PARAM_POLICY,
-            uri_type: UriType::TOTP,
-            key: &self.key,
-            issuer,
-            account_name,
-            custom_label: None,
-            custom_parameters: HashMap::new(),
-            algo: self.hash_function,
-            output_len: self.output_len,
-            output_base: &self.output_base,
-            counter: None,
-            period: Some(self.period),
-            initial_time: Some(self.initial_time),
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1111111109);
+    /// assert_eq!(totp.generate_at_system_time(t).unwrap(), "07081804");
+    /// ```
+    pub fn generate_at_system_time(&self, t: SystemTime) -> Result<String, ErrorCode> {
+        let elapsed = t.duration_since(SystemTime::UNIX_EPOCH)?;
+        self.generate_at(to_time_unit(elapsed, self.milliseconds))
+    }
+
+    /// Returns the intermediate values used to compute the current code. This is useful in order
+    /// to find out why another implementation generates different codes.
+    ///
+    /// **WARNING**: The HMAC allows to compute the code, hence those values should not be
+    /// exposed to anybody but the developers.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(45)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let debug_info = totp.debug_info().unwrap();
+    /// assert_eq!(debug_info.counter, 1);
+    /// assert_eq!(debug_info.truncated_value, 1094287082);
+    /// assert_eq!(debug_info.code, "94287082");
+    /// ```
+    #[cfg(feature = "debug")]
+    pub fn debug_info(&self) -> Result<TOTPDebugInfo, ErrorCode> {
+        self.debug_info_at(self.get_timestamp()?)
+    }
+
+    /// Same as [debug_info](#method.debug_info), for the given Unix time instead of the current
+    /// one. If the millisecond mode is enabled, the timestamp is expressed in milliseconds.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let debug_info = totp.debug_info_at(1111111109).unwrap();
+    /// assert_eq!(debug_info.counter, 37037036);
+    /// assert_eq!(debug_info.code, "07081804");
+    /// ```
+    #[cfg(feature = "debug")]
+    pub fn debug_info_at(&self, timestamp: i64) -> Result<TOTPDebugInfo, ErrorCode> {
+        let counter = self.get_counter_at(timestamp)?;
+        let (hmac, offset, truncated_value, code) = self.get_hotp(counter)?.debug_values(counter);
+        Ok(TOTPDebugInfo {
+            counter,
+            hmac,
+            offset,
+            truncated_value,
+            code,
+        })
+    }
+
+    fn get_hotp(&self, counter: u64) -> Result<HOTP, ErrorCode> {
+        HOTPBuilder::new()
+            .key(&self.key)
+            .counter(counter)
+            .output_len(self.output_len)
+            .output_base(&self.output_base)
+            .hash_function(self.hash_function)
+            .finalize()
+    }
+
+    /// Checks a list of codes against the current period, extended by the positive and negative
+    /// tolerances, and returns the index of the first valid one. If none is valid,
+    /// `ErrorCode::InvalidKey` is returned.
+    ///
+    /// Every code is checked for every period using the [double HMAC
+    /// verification](https://www.nccgroup.trust/us/about-us/newsroom-and-events/blog/2011/february/double-hmac-verification/)
+    /// and the whole list is always processed, so the time taken does not reveal which code is
+    /// valid.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111090)
+    ///     .output_len(8)
+    ///     .tolerance(1)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.verify_multi(&["12345678", "07081804"]).unwrap(), 1);
+    /// assert!(totp.verify_multi(&["12345678"]).is_err());
+    /// ```
+    pub fn verify_multi(&self, codes: &[&str]) -> Result<usize, ErrorCode> {
+        let base_counter = self.get_counter()?;
+        let mut found = Choice::from(0);
+        let mut index = 0u64;
+        for counter in base_counter.saturating_sub(self.negative_tolerance)
+            ..=base_counter.saturating_add(self.positive_tolerance)
+        {
+            let (is_match, i) = self.get_hotp(counter)?.find_code(codes);
+            let is_better = is_match & (!found | i.ct_lt(&index));
+            index.conditional_assign(&i, is_better);
+            found |= is_match;
+        }
+        count_verification!("totp", bool::from(found));
+        if bool::from(found) {
+            Ok(index as usize)
+        } else {
+            Err(ErrorCode::InvalidKey)
         }
     }
-}
 
-/// Builds a TOTP object.
-///
-/// ## Examples
-///
-/// The following examples uses the same shared secret passed in various forms.
-///
-/// ```
-/// let key = vec![49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48];
-/// let mut totp = libreauth::oath::TOTPBuilder::new()
-///     .key(&key)
-///     .finalize()
-///     .unwrap();
-/// ```
+    /// Checks if the given code is valid for the current period, extended by the positive and
+    /// negative tolerances, and returns the counter of the period it matched. If the code is not
+    /// valid, `ErrorCode::InvalidKey` is returned.
+    ///
+    /// Since a code could be used more than once during its validity, which is extended by the
+    /// tolerance, storing the returned counter and rejecting any code whose counter is not
+    /// greater than the last accepted one prevents replay attacks.
+    ///
+    /// Every period of the tolerance window is always checked, so the time taken does not reveal
+    /// which one matched.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111120)
+    ///     .output_len(8)
+    ///     .tolerance(1)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.verify_returning_counter("07081804").unwrap(), 37037036);
+    /// assert!(totp.verify_returning_counter("12345678").is_err());
+    /// ```
+    pub fn verify_returning_counter(&self, code: &str) -> Result<u64, ErrorCode> {
+        let counter = self.find_counter(code, self.get_counter()?);
+        count_verification!("totp", counter.is_ok());
+        counter
+    }
+
+    /// Checks if the given code is valid for the current period, extended by the positive and
+    /// negative tolerances, and returns the number of periods between the current one and the
+    /// one it matched: `0` for the current period, a positive value if the code belongs to a
+    /// later period and a negative one if it belongs to an earlier period. If several periods
+    /// match, the closest one is used. If the code is not valid, `ErrorCode::InvalidKey` is
+    /// returned. If the matched period is more than 127 periods away, `ErrorCode::RangeTooLarge`
+    /// is returned.
+    ///
+    /// Storing the returned value allows to track the clock drift of each client.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111120)
+    ///     .output_len(8)
+    ///     .tolerance(1)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.verify_and_return_skew("07081804").unwrap(), -1);
+    /// assert!(totp.verify_and_return_skew("12345678").is_err());
+    /// ```
+    pub fn verify_and_return_skew(&self, code: &str) -> Result<i8, ErrorCode> {
+        let base_counter = self.get_counter()?;
+        let mut found = Choice::from(0);
+        let mut best_distance = 0u64;
+        let mut best_counter = 0u64;
+        for counter in base_counter.saturating_sub(self.negative_tolerance)
+            ..=base_counter.saturating_add(self.positive_tolerance)
+        {
+            let distance = counter.max(base_counter) - counter.min(base_counter);
+            let is_match = Choice::from(self.get_hotp(counter)?.check_at(code, counter) as u8);
+            let is_better = is_match & (!found | distance.ct_lt(&best_distance));
+            best_distance.conditional_assign(&distance, is_better);
+            best_counter.conditional_assign(&counter, is_better);
+            found |= is_match;
+        }
+        count_verification!("totp", bool::from(found));
+        if !bool::from(found) {
+            return Err(ErrorCode::InvalidKey);
+        }
+        let skew = i8::try_from(best_distance).map_err(|_| ErrorCode::RangeTooLarge)?;
+        if best_counter >= base_counter {
+            Ok(skew)
+        } else {
+            Ok(-skew)
+        }
+    }
+
+    fn find_counter(&self, code: &str, base_counter: u64) -> Result<u64, ErrorCode> {
+        let mut found = Choice::from(0);
+        let mut matched_counter = 0u64;
+        for counter in base_counter.saturating_sub(self.negative_tolerance)
+            ..=base_counter.saturating_add(self.positive_tolerance)
+        {
+            let is_match = Choice::from(self.get_hotp(counter)?.check_at(code, counter) as u8);
+            matched_counter.conditional_assign(&counter, is_match & !found);
+            found |= is_match;
+        }
+        if bool::from(found) {
+            Ok(matched_counter)
+        } else {
+            Err(ErrorCode::InvalidKey)
+        }
+    }
+
+    /// Returns the time left before the current period ends and the next code becomes valid.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111090)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(totp.time_until_next_period().unwrap().as_secs() <= 20);
+    /// ```
+    pub fn time_until_next_period(&self) -> Result<Duration, ErrorCode> {
+        let elapsed = self.get_elapsed(self.get_timestamp()?)? % self.period;
+        let remaining = self.period - elapsed;
+        if self.milliseconds {
+            Ok(Duration::from_millis(remaining))
+        } else {
+            Ok(Duration::from_secs(remaining))
+        }
+    }
+
+    /// Returns the number of seconds elapsed since the current period started, which is the
+    /// complement of [time_until_next_period](#method.time_until_next_period).
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111090)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(totp.seconds_since_step_start().unwrap() >= 10);
+    /// ```
+    pub fn seconds_since_step_start(&self) -> Result<u64, ErrorCode> {
+        self.seconds_since_step_start_at(self.get_timestamp()?)
+    }
+
+    /// Same as [seconds_since_step_start](#method.seconds_since_step_start), for the given Unix
+    /// time instead of the current one. If the millisecond mode is enabled, the timestamp is
+    /// expressed in milliseconds.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.seconds_since_step_start_at(1111111109).unwrap(), 29);
+    /// ```
+    pub fn seconds_since_step_start_at(&self, timestamp: i64) -> Result<u64, ErrorCode> {
+        let elapsed = self.get_elapsed(timestamp)? % self.period;
+        if self.milliseconds {
+            Ok(elapsed / 1000)
+        } else {
+            Ok(elapsed)
+        }
+    }
+
+    /// Estimates the clock drift of the client that generated the given code, in seconds. The
+    /// estimation is the number of periods between the current one and the one the code matched,
+    /// within the tolerance window: it is positive if the client is ahead and negative if it is
+    /// behind. If the code is not valid, `None` is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .timestamp(1111111150)
+    ///     .output_len(8)
+    ///     .tolerance(2)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.clock_drift_detection("07081804"), Some(-60));
+    /// assert_eq!(totp.clock_drift_detection("12345678"), None);
+    /// ```
+    pub fn clock_drift_detection(&self, reference: &str) -> Option<i64> {
+        let base_counter = self.get_counter().ok()?;
+        let counter = self.find_counter(reference, base_counter).ok()?;
+        let steps = if counter >= base_counter {
+            (counter - base_counter) as i64
+        } else {
+            -((base_counter - counter) as i64)
+        };
+        let drift = steps.checked_mul(self.period as i64)?;
+        if self.milliseconds {
+            Some(drift / 1000)
+        } else {
+            Some(drift)
+        }
+    }
+
+    /// Checks if two Unix timestamps fall within the same period, hence share the same code.
+    /// Periods start at the initial time, so timestamps before it never are in the same window.
+    /// If the millisecond mode is enabled, the timestamps are expressed in milliseconds.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert!(totp.is_same_window(1111111080, 1111111109));
+    /// assert!(!totp.is_same_window(1111111109, 1111111110));
+    /// ```
+    pub fn is_same_window(&self, t1: u64, t2: u64) -> bool {
+        let initial_time = self.get_initial_time();
+        let elapsed = |t: u64| {
+            let t = if self.epoch_offset >= 0 {
+                t.checked_add(self.epoch_offset as u64)
+            } else {
+                t.checked_sub(self.epoch_offset.wrapping_neg() as u64)
+            };
+            t?.checked_sub(initial_time)
+        };
+        match (elapsed(t1), elapsed(t2)) {
+            (Some(t1), Some(t2)) => t1 / self.period == t2 / self.period,
+            _ => false,
+        }
+    }
+
+    fn is_compatible(&self) -> bool {
+        let no_ext = self.output_base == DEFAULT_OTP_OUT_BASE
+            && self.initial_time == 0
+            && self.epoch_offset == 0
+            && !self.milliseconds;
+        match self.compatibility {
+            Compatibility::Default => true,
+            Compatibility::GoogleAuthenticator => {
+                no_ext
+                    && self.hash_function == HashFunction::Sha1
+                    && self.output_len == 6
+                    && self.period == 30
+            }
+            Compatibility::MicrosoftAuthenticator => {
+                no_ext
+                    && (self.hash_function == HashFunction::Sha1
+                        || self.hash_function == HashFunction::Sha256
+                        || self.hash_function == HashFunction::Sha512)
+                    && (self.output_len == 6 || self.output_len == 8)
+                    && (self.period == 30 || self.period == 60)
+            }
+        }
+    }
+
+    /// Pads the key with null bytes so its length is a multiple of 5 bytes, hence its base32
+    /// representation does not require any padding. Since HMAC pads the key with null bytes up to
+    /// the hash function's block size, this does not change the generated codes. Keys that would
+    /// exceed the block size are therefore left untouched.
+    fn pad_key_for_base32(&mut self) {
+        let block_size = match self.hash_function {
+            HashFunction::Sha512 => 128,
+            _ => 64,
+        };
+        let padded_len = match self.key.len() % 5 {
+            0 => self.key.len(),
+            r => self.key.len() + 5 - r,
+        };
+        if padded_len <= block_size {
+            self.key.resize(padded_len, 0);
+        }
+    }
+
+    /// Creates the Key Uri Format according to the [Google authenticator
+    /// specification](https://github.com/google/google-authenticator/wiki/Key-Uri-Format).
+    /// This value can be used to generete QR codes which allow easy scanning by the end user.
+    /// The returned [`KeyUriBuilder`] allows for additional customizations.
+    ///
+    /// If `account_name` is empty, the account identifier set using
+    /// [TOTPBuilder::account_identifier](struct.TOTPBuilder.html#method.account_identifier), if
+    /// any, is used instead. The same goes for `issuer` and
+    /// [TOTPBuilder::issuer](struct.TOTPBuilder.html#method.issuer).
+    ///
+    /// **WARNING**: The finalized value contains the secret key of the authentication process and
+    /// should only be displayed to the corresponding user!
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let mut totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let uri = totp
+    ///     .key_uri_format("Provider1", "alice@example.com")
+    ///     .finalize();
+    ///
+    /// assert_eq!(
+    ///     uri,
+    ///     "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1"
+    /// );
+    /// ```
+    #[cfg(feature = "oath-uri")]
+    pub fn key_uri_format<'a>(
+        &'a self,
+        issuer: &'a str,
+        account_name: &'a str,
+    ) -> KeyUriBuilder<'a> {
+        let parameters_visibility = match self.compatibility {
+            Compatibility::Default => DEFAULT_KEY_URI_PARAM_POLICY,
+            _ => {
+                debug_assert!(self.is_compatible());
+                ParametersVisibility::GAuthOnly
+            }
+        };
+        let mut custom_parameters = HashMap::new();
+        if let Some(ref image_url) = self.image_url {
+            custom_parameters.insert("image", image_url.as_str());
+        }
+        let account_name = match self.account_identifier {
+            Some(ref id) if account_name.is_empty() => id.as_str(),
+            _ => account_name,
+        };
+        let issuer = match self.issuer {
+            Some(ref i) if issuer.is_empty() => i.as_str(),
+            _ => issuer,
+        };
+        KeyUriBuilder {
+            parameters_visibility,
+            uri_type: UriType::TOTP,
+            key: &self.key,
+            issuer,
+            account_name,
+            custom_label: None,
+            custom_parameters,
+            algo: self.hash_function,
+            output_len: self.output_len,
+            output_base: &self.output_base,
+            counter: None,
+            period: Some(self.get_period_secs()),
+            initial_time: Some(self.initial_time),
+        }
+    }
+}
+
+/// Builds a TOTP object.
+///
+/// ## Examples
+///
+/// The following examples uses the same shared secret passed in various forms.
+///
+/// ```
+/// let key = vec![49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48];
+/// let mut totp = libreauth::oath::TOTPBuilder::new()
+///     .key(&key)
+///     .finalize()
+///     .unwrap();
+/// ```
 ///
 /// ```
 /// let key_ascii = "12345678901234567890".to_owned();
@@ -200,16 +1149,44 @@ impl TOTP {
 ///     .finalize();
 /// ```
 pub struct TOTPBuilder {
-    key: Option<Vec<u8>>,
-    timestamp_offset: i64,
+    pub(crate) key: Option<Vec<u8>>,
+    timestamp: Option<(i64, Duration)>,
+    epoch_offset: i64,
     positive_tolerance: u64,
     negative_tolerance: u64,
     period: u32,
+    period_ms: Option<u64>,
     initial_time: u64,
-    output_len: usize,
-    output_base: String,
-    hash_function: HashFunction,
-    runtime_error: Option<ErrorCode>,
+    milliseconds: bool,
+    pub(crate) output_len: usize,
+    pub(crate) output_base: String,
+    pub(crate) hash_function: HashFunction,
+    pub(crate) runtime_error: Option<ErrorCode>,
+    compatibility: Compatibility,
+    #[cfg(feature = "oath-uri")]
+    image_url: Option<String>,
+    account_identifier: Option<String>,
+    issuer: Option<String>,
+    #[cfg(test)]
+    fixed_now: Option<i64>,
+}
+
+/// The JSON form of a TOTP builder, whose fields are those of the Key Uri Format.
+#[cfg(feature = "json")]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TOTPBuilderJson {
+    secret: String,
+    #[serde(default)]
+    algorithm: Option<String>,
+    #[serde(default)]
+    digits: Option<usize>,
+    #[serde(default)]
+    period: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    issuer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    account: Option<String>,
 }
 
 impl Default for TOTPBuilder {
@@ -223,27 +1200,115 @@ impl TOTPBuilder {
     pub fn new() -> TOTPBuilder {
         TOTPBuilder {
             key: None,
-            timestamp_offset: 0,
+            timestamp: None,
+            epoch_offset: 0,
             positive_tolerance: 0,
             negative_tolerance: 0,
             period: DEFAULT_TOTP_PERIOD,
+            period_ms: None,
             initial_time: DEFAULT_TOTP_T0,
+            milliseconds: false,
             output_len: DEFAULT_OTP_OUT_LEN,
             output_base: DEFAULT_OTP_OUT_BASE.to_string(),
             hash_function: DEFAULT_OTP_HASH,
             runtime_error: None,
+            compatibility: Compatibility::Default,
+            #[cfg(feature = "oath-uri")]
+            image_url: None,
+            account_identifier: None,
+            issuer: None,
+            #[cfg(test)]
+            fixed_now: None,
+        }
+    }
+
+    /// Generates a configuration from an algorithm string made of the OTP type, the hash
+    /// function, the number of digits and the period, separated by hyphens, such as
+    /// `totp-sha256-8-30`. The comparison is case insensitive and hyphens within the hash
+    /// function name are allowed, hence `TOTP-SHA-256-8-30` is equivalent. If any component
+    /// is not recognized, `ErrorCode::UnsupportedAlgorithm` is returned.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890123456789012".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::for_algorithm("totp-sha256-8-30")
+    ///     .unwrap()
+    ///     .ascii_key(&key_ascii)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate_at(1111111109).unwrap(), "68084774");
+    /// ```
+    pub fn for_algorithm(algo: &str) -> Result<TOTPBuilder, ErrorCode> {
+        let parts: Vec<&str> = algo.split('-').collect();
+        if parts.len() < 4 || !parts[0].eq_ignore_ascii_case("totp") {
+            return Err(ErrorCode::UnsupportedAlgorithm);
         }
+        let hash_function: HashFunction = parts[1..parts.len() - 2].join("-").parse()?;
+        let output_len = parts[parts.len() - 2]
+            .parse::<usize>()
+            .map_err(|_| ErrorCode::UnsupportedAlgorithm)?;
+        let period = parts[parts.len() - 1]
+            .parse::<u32>()
+            .map_err(|_| ErrorCode::UnsupportedAlgorithm)?;
+        let mut builder = TOTPBuilder::new();
+        builder
+            .hash_function(hash_function)
+            .output_len(output_len)
+            .period(period);
+        Ok(builder)
     }
 
     builder_common!(TOTPBuilder);
 
-    /// Sets a custom value for the current Unix time instead of the real one.
+    fn key_min_len(&self) -> usize {
+        KEY_MIN_LEN
+    }
+
+    /// Stops the clock of the generated TOTP at the given Unix time, so tests do not depend on
+    /// the time they run at. If the millisecond mode is enabled, the time is expressed in
+    /// milliseconds.
+    #[cfg(test)]
+    pub(crate) fn fixed_now(&mut self, now: i64) -> &mut TOTPBuilder {
+        self.fixed_now = Some(now);
+        self
+    }
+
+    /// Sets a custom value for the current Unix time instead of the real one. If the millisecond
+    /// mode is enabled, the timestamp is expressed in milliseconds.
     pub fn timestamp(&mut self, timestamp: i64) -> &mut TOTPBuilder {
-        let current_timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-        self.timestamp_offset = timestamp - current_timestamp;
+        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(d) => {
+                self.timestamp = Some((timestamp, d));
+            }
+            Err(e) => {
+                self.runtime_error = Some(e.into());
+            }
+        }
+        self
+    }
+
+    /// Sets the offset, in seconds, between the Unix epoch and the epoch used by the token. This
+    /// offset is added to the Unix time before computing the time steps: a negative offset means
+    /// the token's epoch is after the Unix epoch. If the resulting time cannot be represented,
+    /// `ErrorCode::TimestampOverflow` is returned. Default is 0.
+    ///
+    /// This is a LibreAuth extension and is not included in key URIs.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .output_len(8)
+    ///     .epoch_offset_seconds(-1_000_000_000)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate_at(2111111109).unwrap(), "07081804");
+    /// ```
+    pub fn epoch_offset_seconds(&mut self, offset: i64) -> &mut TOTPBuilder {
+        self.epoch_offset = offset;
         self
     }
 
@@ -275,55 +1340,453 @@ impl TOTPBuilder {
             self.runtime_error = Some(ErrorCode::InvalidPeriod);
         } else {
             self.period = period;
+            self.period_ms = None;
         }
         self
     }
 
-    /// Sets the Unix time to start counting time steps (T0). Default is 0.
-    pub fn initial_time(&mut self, initial_time: u64) -> &mut TOTPBuilder {
-        self.initial_time = initial_time;
+    /// Sets the time step in milliseconds (X). May not be zero. Unless the millisecond mode is
+    /// enabled, the period must be a whole number of seconds, otherwise `finalize` fails with
+    /// `ErrorCode::InvalidPeriod`.
+    pub fn period_ms(&mut self, period: u64) -> &mut TOTPBuilder {
+        if period == 0 {
+            self.runtime_error = Some(ErrorCode::InvalidPeriod);
+        } else {
+            self.period_ms = Some(period);
+        }
         self
     }
 
-    /// Returns the finalized TOTP object.
-    pub fn finalize(&self) -> Result<TOTP, ErrorCode> {
-        if let Some(e) = self.runtime_error {
-            return Err(e);
-        }
-        match self.code_length() {
-            n if n < 1_000_000 => return Err(ErrorCode::CodeTooSmall),
-            n if n > 2_147_483_648 => return Err(ErrorCode::CodeTooBig),
-            _ => (),
-        }
-        match self.key {
-            Some(ref k) => Ok(TOTP {
-                key: k.clone(),
-                timestamp_offset: self.timestamp_offset,
-                positive_tolerance: self.positive_tolerance,
-                negative_tolerance: self.negative_tolerance,
-                initial_time: self.initial_time,
-                period: self.period,
-                output_len: self.output_len,
-                output_base: self.output_base.clone(),
-                hash_function: self.hash_function,
-            }),
-            None => Err(ErrorCode::InvalidKey),
-        }
+    /// Enables or disables the millisecond mode. Once enabled, the current time is measured in
+    /// milliseconds and the timestamps passed to or returned by the TOTP object are also expressed
+    /// in milliseconds, which allows periods shorter than a second or not aligned on one. The
+    /// initial time is still expressed in seconds. Default is disabled.
+    ///
+    /// This is a LibreAuth extension: key URIs cannot express it and authenticator applications do
+    /// not support it.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .use_milliseconds(true)
+    ///     .period_ms(500)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate_at(1000).unwrap(), totp.generate_at(1499).unwrap());
+    /// assert_ne!(totp.generate_at(1499).unwrap(), totp.generate_at(1500).unwrap());
+    /// ```
+    pub fn use_milliseconds(&mut self, enabled: bool) -> &mut TOTPBuilder {
+        self.milliseconds = enabled;
+        self
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::TOTPBuilder;
-    use crate::hash::HashFunction;
-
-    #[test]
-    fn test_totp_key_simple() {
-        let key = vec![
-            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
-        ];
 
-        let totp = TOTPBuilder::new().key(&key).finalize().unwrap();
+    /// Sets the URL of an image, such as the issuer's logo, that some authenticator applications
+    /// display next to the code. It is added to the key URI as the unofficial `image` parameter.
+    /// Only `https` URLs are accepted: otherwise, `finalize` fails with `ErrorCode::InvalidUri`.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .image_url("https://example.com/logo.png")
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let uri = totp
+    ///     .key_uri_format("Provider1", "alice@example.com")
+    ///     .finalize();
+    /// assert!(uri.ends_with("&image=https%3A%2F%2Fexample.com%2Flogo.png"));
+    /// ```
+    #[cfg(feature = "oath-uri")]
+    pub fn image_url(&mut self, url: &str) -> &mut TOTPBuilder {
+        match url::Url::parse(url) {
+            Ok(ref u) if u.scheme() == "https" => {
+                self.image_url = Some(url.to_string());
+            }
+            _ => {
+                self.runtime_error = Some(ErrorCode::InvalidUri);
+            }
+        }
+        self
+    }
+
+    /// Associates the TOTP with an account, for example the user name. This identifier is
+    /// informational only and does not change the generated codes.
+    ///
+    /// It is used as the account name of the key URI when `key_uri_format` is given an empty one.
+    /// Since it may be personal information, it only appears in the log messages (with the
+    /// `logging` feature) and in the `Debug` output of the `TOTP` if the `pii-logging` feature is
+    /// enabled. Otherwise, it is replaced by `***`.
+    ///
+    /// ## Examples
+    /// ```
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .account_identifier("alice@example.com")
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.account_identifier(), Some("alice@example.com"));
+    /// ```
+    pub fn account_identifier(&mut self, id: &str) -> &mut TOTPBuilder {
+        self.account_identifier = Some(id.to_string());
+        self
+    }
+
+    /// Sets the provider or service the TOTP is associated with. Like the account identifier,
+    /// it does not change the generated codes and is used by `key_uri_format` when given an
+    /// empty issuer.
+    pub fn issuer(&mut self, issuer: &str) -> &mut TOTPBuilder {
+        self.issuer = Some(issuer.to_string());
+        self
+    }
+
+    /// Sets the Unix time to start counting time steps (T0). Default is 0.
+    pub fn initial_time(&mut self, initial_time: u64) -> &mut TOTPBuilder {
+        self.initial_time = initial_time;
+        self
+    }
+
+    /// Sets the configuration expected by Google Authenticator: SHA1, 6 digits and a 30 seconds
+    /// period, without any LibreAuth extension. Since this application silently misinterprets
+    /// any other configuration, changing those parameters afterwards will make `finalize` fail
+    /// with `ErrorCode::IncompatibleOption`. The generated key URI only contains parameters known
+    /// by Google Authenticator.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::oath::TOTPBuilder;
+    ///
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .google_authenticator_compat()
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let res = TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .google_authenticator_compat()
+    ///     .output_len(8)
+    ///     .finalize();
+    /// assert!(res.is_err());
+    /// ```
+    pub fn google_authenticator_compat(&mut self) -> &mut TOTPBuilder {
+        self.hash_function = HashFunction::Sha1;
+        self.output_len = 6;
+        self.output_base = DEFAULT_OTP_OUT_BASE.to_string();
+        self.period = 30;
+        self.period_ms = None;
+        self.initial_time = 0;
+        self.epoch_offset = 0;
+        self.milliseconds = false;
+        self.compatibility = Compatibility::GoogleAuthenticator;
+        self
+    }
+
+    /// Restricts the configuration to the one supported by Microsoft Authenticator: SHA1, SHA256
+    /// or SHA512, 6 or 8 digits and a period of 30 or 60 seconds, without any LibreAuth extension.
+    /// Any other configuration will make `finalize` fail with `ErrorCode::IncompatibleOption`.
+    ///
+    /// Because this application has known issues with padded base32 secrets, the key is extended
+    /// with null bytes to a multiple of 5 bytes, which does not change the generated codes. Also,
+    /// some versions of this application do not handle SHA512 correctly, therefore SHA1 should be
+    /// preferred and, if the `logging` feature is enabled, `finalize` emits a warning when SHA512
+    /// is selected.
+    ///
+    /// ## Examples
+    /// ```
+    /// use libreauth::hash::HashFunction;
+    /// use libreauth::oath::TOTPBuilder;
+    ///
+    /// let key_ascii = "12345678901234567890".to_owned();
+    /// let totp = TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .microsoft_authenticator_compat()
+    ///     .hash_function(HashFunction::Sha256)
+    ///     .output_len(8)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// let res = TOTPBuilder::new()
+    ///     .ascii_key(&key_ascii)
+    ///     .microsoft_authenticator_compat()
+    ///     .period(42)
+    ///     .finalize();
+    /// assert!(res.is_err());
+    /// ```
+    pub fn microsoft_authenticator_compat(&mut self) -> &mut TOTPBuilder {
+        self.compatibility = Compatibility::MicrosoftAuthenticator;
+        self
+    }
+
+    /// Serializes the configuration into a JSON object whose fields are those of the Key Uri
+    /// Format: `secret` (base32 encoded), `algorithm`, `digits`, `period` and, if set, `issuer`
+    /// and `account` (the account identifier).
+    ///
+    /// The other parameters, such as the tolerance, are not part of the JSON object. However, if
+    /// a parameter which changes the generated codes (the output base, the initial time, the epoch
+    /// offset or the millisecond mode) has been changed, `ErrorCode::IncompatibleOption` is
+    /// returned. Configuration errors are returned the same way `finalize` does.
+    ///
+    /// ## Examples
+    /// ```
+    /// let json = libreauth::oath::TOTPBuilder::new()
+    ///     .ascii_key("12345678901234567890")
+    ///     .output_len(8)
+    ///     .account_identifier("alice@example.com")
+    ///     .to_json()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     json,
+    ///     r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","algorithm":"SHA1","digits":8,"period":30,"account":"alice@example.com"}"#
+    /// );
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, ErrorCode> {
+        if let Some(e) = self.runtime_error {
+            return Err(e);
+        }
+        let key = self.key.as_ref().ok_or(ErrorCode::InvalidKey)?;
+        if self.output_base != DEFAULT_OTP_OUT_BASE
+            || self.initial_time != DEFAULT_TOTP_T0
+            || self.epoch_offset != 0
+            || self.milliseconds
+        {
+            return Err(ErrorCode::IncompatibleOption);
+        }
+        let period = match self.period_ms {
+            Some(ms) if ms % 1000 == 0 && ms / 1000 <= 0xffff_ffff => (ms / 1000) as u32,
+            Some(_) => return Err(ErrorCode::InvalidPeriod),
+            None => self.period,
+        };
+        let json = TOTPBuilderJson {
+            secret: base32_encode(key),
+            algorithm: Some(self.hash_function.to_string()),
+            digits: Some(self.output_len),
+            period: Some(period),
+            issuer: self.issuer.clone(),
+            account: self.account_identifier.clone(),
+        };
+        serde_json::to_string(&json).map_err(|_| ErrorCode::InvalidJson)
+    }
+
+    /// Creates a builder from a JSON object as generated by [to_json](#method.to_json). Only
+    /// the `secret` field is required, the other ones having the same default values as the
+    /// builder.
+    ///
+    /// An invalid JSON document, an unknown field or a field of the wrong type results in an
+    /// `ErrorCode::InvalidJson` error. Field values are then checked the same way `finalize`
+    /// does: for example, an invalid secret results in an `ErrorCode::InvalidKey` error and an
+    /// unknown algorithm in an `ErrorCode::UnsupportedAlgorithm` error.
+    ///
+    /// ## Examples
+    /// ```
+    /// let json = r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","digits":8}"#;
+    /// let totp = libreauth::oath::TOTPBuilder::from_json(json)
+    ///     .unwrap()
+    ///     .timestamp(1111111090)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(totp.generate(), "07081804");
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_json(s: &str) -> Result<TOTPBuilder, ErrorCode> {
+        let json: TOTPBuilderJson = serde_json::from_str(s).map_err(|_| ErrorCode::InvalidJson)?;
+        let mut builder = TOTPBuilder::new();
+        builder.base32_key(&json.secret);
+        if let Some(ref algorithm) = json.algorithm {
+            let hash_function = algorithm
+                .parse()
+                .map_err(|_| ErrorCode::UnsupportedAlgorithm)?;
+            builder.hash_function(hash_function);
+        }
+        if let Some(digits) = json.digits {
+            builder.output_len(digits);
+        }
+        if let Some(period) = json.period {
+            builder.period(period);
+        }
+        if let Some(ref issuer) = json.issuer {
+            builder.issuer(issuer);
+        }
+        if let Some(ref account) = json.account {
+            builder.account_identifier(account);
+        }
+        builder.build()?;
+        Ok(builder)
+    }
+
+    /// Returns the finalized TOTP object.
+    pub fn finalize(&self) -> Result<TOTP, ErrorCode> {
+        let totp = self.build();
+        if totp.is_err() {
+            count_event!("libreauth.config_error_count");
+        }
+        if self.compatibility == Compatibility::MicrosoftAuthenticator
+            && self.hash_function == HashFunction::Sha512
+        {
+            log_warn!("some versions of Microsoft Authenticator do not handle SHA512 correctly");
+        }
+        totp
+    }
+
+    // Returns the period in the time unit of the TOTP object, or `None` if a period in
+    // milliseconds which is not a whole number of seconds has been set without enabling the
+    // millisecond mode.
+    fn get_period(&self) -> Option<u64> {
+        match (self.milliseconds, self.period_ms) {
+            (true, Some(ms)) => Some(ms),
+            (true, None) => Some(u64::from(self.period) * 1000),
+            (false, Some(ms)) if ms % 1000 == 0 => Some(ms / 1000),
+            (false, Some(_)) => None,
+            (false, None) => Some(u64::from(self.period)),
+        }
+    }
+
+    /// Returns the counter used to compute the code at the given timestamp, that is
+    /// `(timestamp - T0) / X` with the configured initial time (T0), period (X) and epoch offset.
+    /// The timestamp is expressed in seconds or, if the millisecond mode is enabled, in
+    /// milliseconds. This allows to compute the expected counters without computing any code.
+    ///
+    /// Instead of an error, `u64::MAX` is returned if the timestamp is before the initial time, if
+    /// the counter would overflow or if the period is invalid, `finalize` reporting the latter
+    /// with `ErrorCode::InvalidPeriod`.
+    ///
+    /// ## Examples
+    /// ```
+    /// let mut builder = libreauth::oath::TOTPBuilder::new();
+    /// assert_eq!(builder.time_based_counter(59), 1);
+    /// assert_eq!(builder.time_based_counter(1111111109), 37037036);
+    ///
+    /// builder.period(60).initial_time(60);
+    /// assert_eq!(builder.time_based_counter(59), u64::max_value());
+    /// assert_eq!(builder.time_based_counter(1111111109), 18518517);
+    /// ```
+    pub fn time_based_counter(&self, timestamp: u64) -> u64 {
+        let (initial_time, epoch_offset) = if self.milliseconds {
+            (
+                i128::from(self.initial_time) * 1000,
+                i128::from(self.epoch_offset) * 1000,
+            )
+        } else {
+            (i128::from(self.initial_time), i128::from(self.epoch_offset))
+        };
+        let elapsed = i128::from(timestamp) + epoch_offset - initial_time;
+        let counter = match self.get_period() {
+            Some(period) if period != 0 && elapsed >= 0 => {
+                u64::try_from(elapsed as u128 / u128::from(period)).ok()
+            }
+            _ => None,
+        };
+        counter.unwrap_or(INVALID_COUNTER)
+    }
+
+    fn build(&self) -> Result<TOTP, ErrorCode> {
+        if let Some(e) = self.runtime_error {
+            return Err(e);
+        }
+        self.check_output_base()?;
+        match self.code_length() {
+            n if n < 1_000_000 => return Err(ErrorCode::CodeTooSmall),
+            n if n > 2_147_483_648 => return Err(ErrorCode::CodeTooBig),
+            _ => (),
+        }
+        let period = self.get_period().ok_or(ErrorCode::InvalidPeriod)?;
+        let epoch_offset = if self.milliseconds {
+            self.epoch_offset
+                .checked_mul(1000)
+                .ok_or(ErrorCode::TimestampOverflow)?
+        } else {
+            self.epoch_offset
+        };
+        let timestamp_offset = match self.timestamp {
+            Some((timestamp, now)) => timestamp - to_time_unit(now, self.milliseconds),
+            None => 0,
+        };
+        match self.key {
+            Some(ref k) => {
+                let mut totp = TOTP {
+                    key: k.clone(),
+                    timestamp_offset,
+                    epoch_offset,
+                    positive_tolerance: self.positive_tolerance,
+                    negative_tolerance: self.negative_tolerance,
+                    initial_time: self.initial_time,
+                    period,
+                    milliseconds: self.milliseconds,
+                    output_len: self.output_len,
+                    output_base: self.output_base.clone(),
+                    hash_function: self.hash_function,
+                    compatibility: self.compatibility,
+                    #[cfg(feature = "oath-uri")]
+                    image_url: self.image_url.clone(),
+                    account_identifier: self.account_identifier.clone(),
+                    issuer: self.issuer.clone(),
+                    #[cfg(test)]
+                    fixed_now: self.fixed_now,
+                };
+                if !totp.is_compatible() {
+                    return Err(ErrorCode::IncompatibleOption);
+                }
+                if totp.compatibility == Compatibility::MicrosoftAuthenticator {
+                    totp.pad_key_for_base32();
+                }
+                Ok(totp)
+            }
+            None => Err(ErrorCode::InvalidKey),
+        }
+    }
+}
+
+impl From<HOTPBuilder> for TOTPBuilder {
+    /// Creates a TOTP builder sharing the key, hash function, output length and output base of an
+    /// HOTP builder. The other parameters are set to their default values.
+    fn from(builder: HOTPBuilder) -> Self {
+        TOTPBuilder {
+            key: builder.key,
+            output_len: builder.output_len,
+            output_base: builder.output_base,
+            hash_function: builder.hash_function,
+            runtime_error: builder.runtime_error,
+            ..TOTPBuilder::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TOTPBuilder;
+    #[cfg(feature = "debug")]
+    use super::TOTPDebugInfo;
+    #[cfg(feature = "serde")]
+    use super::TOTP;
+    use crate::hash::HashFunction;
+    use crate::oath::{ErrorCode, HOTPBuilder};
+    use std::convert::TryFrom;
+    use std::time::{Duration, SystemTime};
+
+    /// Builds a TOTP builder using the given ASCII key whose clock is fixed at the given Unix time.
+    macro_rules! totp_at_time {
+        ($ts: expr, $key: expr) => {
+            TOTPBuilder::new().ascii_key($key).fixed_now($ts)
+        };
+    }
+
+    #[test]
+    fn test_totp_key_simple() {
+        let key = vec![
+            49, 50, 51, 52, 53, 54, 55, 56, 57, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 48,
+        ];
+
+        let totp = TOTPBuilder::new().key(&key).finalize().unwrap();
 
         assert_eq!(totp.key, key);
         assert_eq!(totp.output_len, 6);
@@ -546,6 +2009,27 @@ mod tests {
         assert_eq!(code, "04696041");
     }
 
+    #[test]
+    fn test_from_hotp_builder() {
+        let mut hotp = HOTPBuilder::new();
+        hotp.ascii_key("12345678901234567890")
+            .counter(42)
+            .output_len(7)
+            .output_base("0123456789abcdef")
+            .hash_function(HashFunction::Sha256);
+        let totp = TOTPBuilder::from(hotp).finalize().unwrap();
+        assert_eq!(totp.key, b"12345678901234567890".to_vec());
+        assert_eq!(totp.output_len, 7);
+        assert_eq!(totp.output_base, "0123456789abcdef");
+        assert!(totp.hash_function == HashFunction::Sha256);
+        assert_eq!(totp.period, 30);
+        assert_eq!(totp.initial_time, 0);
+
+        let mut hotp = HOTPBuilder::new();
+        hotp.hex_key("invalid key");
+        assert!(TOTPBuilder::from(hotp).finalize().is_err());
+    }
+
     #[test]
     fn test_nokey() {
         match TOTPBuilder::new().finalize() {
@@ -704,197 +2188,1324 @@ mod tests {
     fn test_rfc6238_examples_sha256() {
         let key_hex = "3132333435363738393031323334353637383930313233343536373839303132".to_owned();
         let examples = [
-            (59, HashFunction::Sha256, "46119246"),
-            (1111111109, HashFunction::Sha256, "68084774"),
-            (1111111111, HashFunction::Sha256, "67062674"),
-            (1234567890, HashFunction::Sha256, "91819424"),
-            (2000000000, HashFunction::Sha256, "90698825"),
-            (20000000000, HashFunction::Sha256, "77737706"),
+            (59, HashFunction::Sha256, "46119246"),
+            (1111111109, HashFunction::Sha256, "68084774"),
+            (1111111111, HashFunction::Sha256, "67062674"),
+            (1234567890, HashFunction::Sha256, "91819424"),
+            (2000000000, HashFunction::Sha256, "90698825"),
+            (20000000000, HashFunction::Sha256, "77737706"),
+        ];
+        for &(timestamp, hash_function, ref_code) in examples.iter() {
+            let code = TOTPBuilder::new()
+                .hex_key(&key_hex)
+                .timestamp(timestamp)
+                .output_len(8)
+                .hash_function(hash_function)
+                .finalize()
+                .unwrap()
+                .generate();
+            assert_eq!(code.len(), 8);
+            assert_eq!(code, ref_code);
+        }
+    }
+
+    #[test]
+    fn test_rfc6238_examples_sha512() {
+        let key_hex = "31323334353637383930313233343536373839303132333435363738393031323334353637383930313233343536373839303132333435363738393031323334".to_owned();
+        let examples = [
+            (59, HashFunction::Sha512, "90693936"),
+            (1111111109, HashFunction::Sha512, "25091201"),
+            (1111111111, HashFunction::Sha512, "99943326"),
+            (1234567890, HashFunction::Sha512, "93441116"),
+            (2000000000, HashFunction::Sha512, "38618901"),
+            (20000000000, HashFunction::Sha512, "47863826"),
+        ];
+        for &(timestamp, hash_function, ref_code) in examples.iter() {
+            let code = TOTPBuilder::new()
+                .hex_key(&key_hex)
+                .timestamp(timestamp)
+                .output_len(8)
+                .hash_function(hash_function)
+                .finalize()
+                .unwrap()
+                .generate();
+            assert_eq!(code.len(), 8);
+            assert_eq!(code, ref_code);
+        }
+    }
+
+    #[test]
+    fn test_valid_code() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let user_code = "94287082".to_owned();
+        let valid = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .timestamp(59)
+            .output_len(8)
+            .finalize()
+            .unwrap()
+            .is_valid(&user_code);
+        assert_eq!(valid, true);
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let examples = [
+            (1234567890, 0, "590587", false), // +1
+            (1234567890, 1, "590587", true),  // +1
+            (1234567890, 1, "240500", false), // +2
+            (1234567890, 2, "240500", true),  // +2
+            (1234567890, 0, "980357", false), // -1
+            (1234567890, 1, "980357", true),  // -1
+            (1234567890, 1, "186057", false), // -2
+            (1234567890, 2, "186057", true),  // -2
+        ];
+        for &(timestamp, tolerance, user_code, validity) in examples.iter() {
+            let valid = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .timestamp(timestamp)
+                .tolerance(tolerance)
+                .finalize()
+                .unwrap()
+                .is_valid(&user_code.to_owned());
+            assert_eq!(valid, validity);
+        }
+    }
+
+    #[test]
+    fn test_positive_tolerance() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let examples = [
+            (1234567890, 0, "590587", false), // +1
+            (1234567890, 1, "590587", true),  // +1
+            (1234567890, 1, "240500", false), // +2
+            (1234567890, 2, "240500", true),  // +2
+            (1234567890, 0, "980357", false), // -1
+            (1234567890, 1, "980357", false), // -1
+            (1234567890, 1, "186057", false), // -2
+            (1234567890, 2, "186057", false), // -2
+        ];
+        for &(timestamp, tolerance, user_code, validity) in examples.iter() {
+            let valid = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .timestamp(timestamp)
+                .positive_tolerance(tolerance)
+                .finalize()
+                .unwrap()
+                .is_valid(&user_code.to_owned());
+            assert_eq!(valid, validity);
+        }
+    }
+
+    #[test]
+    fn test_negative_tolerance() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let examples = [
+            (1234567890, 0, "590587", false), // +1
+            (1234567890, 1, "590587", false), // +1
+            (1234567890, 1, "240500", false), // +2
+            (1234567890, 2, "240500", false), // +2
+            (1234567890, 0, "980357", false), // -1
+            (1234567890, 1, "980357", true),  // -1
+            (1234567890, 1, "186057", false), // -2
+            (1234567890, 2, "186057", true),  // -2
+        ];
+        for &(timestamp, tolerance, user_code, validity) in examples.iter() {
+            let valid = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .timestamp(timestamp)
+                .negative_tolerance(tolerance)
+                .finalize()
+                .unwrap()
+                .is_valid(&user_code.to_owned());
+            assert_eq!(valid, validity);
+        }
+    }
+
+    #[test]
+    fn test_invalid_code() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let user_code = "12345678".to_owned();
+        let valid = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .timestamp(59)
+            .output_len(8)
+            .finalize()
+            .unwrap()
+            .is_valid(&user_code);
+        assert_eq!(valid, false);
+    }
+
+    #[test]
+    fn test_bad_code() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let user_code = "!@#$%^&*".to_owned();
+        let valid = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .timestamp(59)
+            .output_len(8)
+            .finalize()
+            .unwrap()
+            .is_valid(&user_code);
+        assert_eq!(valid, false);
+    }
+
+    #[test]
+    fn test_empty_code() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let user_code = "".to_owned();
+        let valid = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .timestamp(59)
+            .output_len(8)
+            .finalize()
+            .unwrap()
+            .is_valid(&user_code);
+        assert_eq!(valid, false);
+    }
+
+    #[test]
+    #[cfg(feature = "oath-uri")]
+    fn test_key_uri_format() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new().ascii_key(&key_ascii).finalize().unwrap();
+
+        let uri = totp
+            .key_uri_format("Provider1", "alice@example.com")
+            .finalize();
+
+        assert_eq!(
+            uri,
+            "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1"
+        );
+    }
+
+    #[test]
+    fn test_verify_multi() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let mut builder = TOTPBuilder::new();
+        builder
+            .ascii_key(&key_ascii)
+            .timestamp(1111111090)
+            .output_len(8);
+        let totp = builder.finalize().unwrap();
+        assert_eq!(totp.verify_multi(&["07081804"]).unwrap(), 0);
+        assert_eq!(totp.verify_multi(&["", "07081804", "07081804"]).unwrap(), 1);
+        match totp.verify_multi(&["14050471", "12345678"]) {
+            Err(ErrorCode::InvalidKey) => {}
+            _ => panic!("invalid code accepted"),
+        }
+        assert!(totp.verify_multi(&[]).is_err());
+
+        let totp = builder.tolerance(1).finalize().unwrap();
+        assert_eq!(totp.verify_multi(&["14050471", "12345678"]).unwrap(), 0);
+        assert_eq!(totp.verify_multi(&["12345678", "14050471"]).unwrap(), 1);
+        assert_eq!(totp.verify_multi(&["14050471", "07081804"]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_time_until_next_period() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let examples = [
+            (1111111080, 30, 0, 30),
+            (1111111109, 30, 0, 1),
+            (1111111110, 30, 0, 30),
+            (100, 60, 30, 50),
+            (89, 60, 30, 1),
+            (90, 60, 30, 60),
+        ];
+        for &(timestamp, period, initial_time, remaining) in examples.iter() {
+            let totp = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .timestamp(timestamp)
+                .period(period)
+                .initial_time(initial_time)
+                .finalize()
+                .unwrap();
+            assert_eq!(
+                totp.time_until_next_period(),
+                Ok(Duration::from_secs(remaining))
+            );
+        }
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .timestamp(10)
+            .initial_time(20)
+            .finalize()
+            .unwrap();
+        assert_eq!(
+            totp.time_until_next_period(),
+            Err(ErrorCode::TimestampOverflow)
+        );
+
+        let totp = TOTPBuilder::new().ascii_key(&key_ascii).finalize().unwrap();
+        let remaining = totp.time_until_next_period().unwrap();
+        assert!(remaining > Duration::from_secs(0));
+        assert!(remaining <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_epoch_offset() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let examples = [
+            (0, 1111111109, "07081804"),
+            (-1_000_000_000, 2111111109, "07081804"),
+            (1_000_000_000, 111111109, "07081804"),
+            (-1111111109, 2222222218, "07081804"),
+        ];
+        for &(offset, timestamp, code) in examples.iter() {
+            let totp = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .output_len(8)
+                .epoch_offset_seconds(offset)
+                .timestamp(timestamp)
+                .finalize()
+                .unwrap();
+            assert_eq!(totp.generate_at(timestamp).unwrap(), code);
+            assert_eq!(totp.generate(), code);
+            assert!(totp.is_valid(code));
+            assert!(totp.is_same_window(timestamp as u64, timestamp as u64 - 29));
+            assert!(!totp.is_same_window(timestamp as u64, timestamp as u64 + 1));
+        }
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .epoch_offset_seconds(-100)
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.generate_at(99), Err(ErrorCode::TimestampOverflow));
+        assert!(totp.generate_at(100).is_ok());
+        assert!(!totp.is_same_window(42, 42));
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .epoch_offset_seconds(i64::max_value())
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.generate_at(1), Err(ErrorCode::TimestampOverflow));
+
+        let res = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .use_milliseconds(true)
+            .epoch_offset_seconds(i64::max_value() / 100)
+            .finalize();
+        assert_eq!(res.err(), Some(ErrorCode::TimestampOverflow));
+
+        let res = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .google_authenticator_compat()
+            .epoch_offset_seconds(1)
+            .finalize();
+        assert_eq!(res.err(), Some(ErrorCode::IncompatibleOption));
+    }
+
+    #[test]
+    fn test_milliseconds() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(8)
+            .use_milliseconds(true)
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.generate_at(1111111109000).unwrap(), "07081804");
+        assert_eq!(totp.generate_at(1111111109999).unwrap(), "07081804");
+        assert!(totp.is_valid_at("07081804", 1111111080000));
+        assert!(!totp.is_valid_at("07081804", 1111111079999));
+        assert!(!totp.is_valid_at("07081804", 1111111110000));
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .use_milliseconds(true)
+            .period_ms(500)
+            .initial_time(1)
+            .tolerance(1)
+            .timestamp(1750)
+            .finalize()
+            .unwrap();
+        assert!(totp.is_same_window(1000, 1499));
+        assert!(!totp.is_same_window(1499, 1500));
+        assert!(!totp.is_same_window(999, 999));
+        assert_eq!(totp.generate_at(999), Err(ErrorCode::TimestampOverflow));
+        assert_eq!(
+            totp.generate_at(1500).unwrap(),
+            totp.get_hotp(1).unwrap().generate()
+        );
+        // The custom timestamp follows the real clock, which may have moved by a few milliseconds.
+        let remaining = totp.time_until_next_period().unwrap();
+        assert!(remaining <= Duration::from_millis(250));
+        assert!(remaining > Duration::from_millis(150));
+        let code = totp.generate_at(1250).unwrap();
+        assert_eq!(totp.verify_returning_counter(&code), Ok(0));
+        assert!(totp.is_valid(&code));
+    }
+
+    #[test]
+    fn test_period_ms() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .period_ms(60_000)
+            .finalize()
+            .unwrap();
+        let reference = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .period(60)
+            .finalize()
+            .unwrap();
+        assert_eq!(
+            totp.generate_at(1111111109).unwrap(),
+            reference.generate_at(1111111109).unwrap()
+        );
+
+        let res = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .period_ms(1500)
+            .finalize();
+        assert_eq!(res.err(), Some(ErrorCode::InvalidPeriod));
+        let res = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .use_milliseconds(true)
+            .period_ms(0)
+            .finalize();
+        assert_eq!(res.err(), Some(ErrorCode::InvalidPeriod));
+
+        // The last period set wins.
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .period_ms(1500)
+            .period(60)
+            .finalize()
+            .unwrap();
+        assert!(totp.is_same_window(60, 119));
+        assert!(!totp.is_same_window(119, 120));
+    }
+
+    #[test]
+    fn test_milliseconds_compatibility() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let res = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .google_authenticator_compat()
+            .use_milliseconds(true)
+            .finalize();
+        assert_eq!(res.err(), Some(ErrorCode::IncompatibleOption));
+        let res = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .microsoft_authenticator_compat()
+            .use_milliseconds(true)
+            .finalize();
+        assert_eq!(res.err(), Some(ErrorCode::IncompatibleOption));
+    }
+
+    #[test]
+    fn test_clock_drift_detection() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = totp_at_time!(1111111109, &key_ascii)
+            .period(60)
+            .tolerance(2)
+            .finalize()
+            .unwrap();
+        let examples = [
+            (1111111109 - 120, Some(-120)),
+            (1111111109 - 60, Some(-60)),
+            (1111111109, Some(0)),
+            (1111111109 + 60, Some(60)),
+            (1111111109 + 120, Some(120)),
+            (1111111109 + 180, None),
+        ];
+        for &(timestamp, drift) in examples.iter() {
+            let code = totp.generate_at(timestamp).unwrap();
+            assert_eq!(totp.clock_drift_detection(&code), drift);
+        }
+        assert_eq!(totp.clock_drift_detection(""), None);
+    }
+
+    #[test]
+    fn test_seconds_since_step_start() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let examples = [
+            (1111111080, 30, 0, 0),
+            (1111111109, 30, 0, 29),
+            (1111111110, 30, 0, 0),
+            (100, 60, 30, 10),
+            (89, 60, 30, 59),
+        ];
+        for &(timestamp, period, initial_time, elapsed) in examples.iter() {
+            let totp = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .period(period)
+                .initial_time(initial_time)
+                .finalize()
+                .unwrap();
+            assert_eq!(totp.seconds_since_step_start_at(timestamp), Ok(elapsed));
+        }
+
+        let totp = totp_at_time!(1111111109, &key_ascii).finalize().unwrap();
+        assert_eq!(totp.seconds_since_step_start(), Ok(29));
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .initial_time(20)
+            .finalize()
+            .unwrap();
+        assert_eq!(
+            totp.seconds_since_step_start_at(10),
+            Err(ErrorCode::TimestampOverflow)
+        );
+        let totp = totp_at_time!(10, &key_ascii)
+            .initial_time(20)
+            .finalize()
+            .unwrap();
+        assert_eq!(
+            totp.seconds_since_step_start(),
+            Err(ErrorCode::TimestampOverflow)
+        );
+    }
+
+    #[test]
+    fn test_clone() {
+        let key = "12345678901234567890";
+        let totp = totp_at_time!(1111111080, key)
+            .output_len(8)
+            .tolerance(1)
+            .finalize()
+            .unwrap();
+        let copy = totp.clone();
+        drop(totp);
+        assert_eq!(copy.key, key.as_bytes());
+        assert_eq!(copy.generate(), "07081804");
+        assert!(copy.verify("07081804"));
+        assert!(copy.verify(&copy.generate_next().unwrap()));
+        assert!(!copy.verify("07081805"));
+    }
+
+    #[test]
+    fn test_debug_redacts_key() {
+        let totp = totp_at_time!(1111111080, "12345678901234567890")
+            .finalize()
+            .unwrap();
+        let debug = format!("{:?}", totp);
+        assert!(debug.contains("key: ***"));
+        assert!(debug.contains("hash_function: SHA1"));
+        assert!(!debug.contains("49, 50"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        // The timestamp offset is part of the serialized configuration, unlike the fixed clock.
+        let totp = TOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .timestamp(1111111080)
+            .output_len(8)
+            .hash_function(HashFunction::Sha256)
+            .finalize()
+            .unwrap();
+        let json = serde_json::to_string(&totp).unwrap();
+        assert!(json.contains(r#""key":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ""#));
+        assert!(json.contains(r#""hash_function":"SHA256""#));
+        let copy: TOTP = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.generate(), totp.generate());
+        assert_eq!(serde_json::to_string(&copy).unwrap(), json);
+
+        let invalid = [
+            json.replace("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ", "GEZDGNBV!Y3TQOJQ"),
+            json.replace("SHA256", "MD5"),
+            json.replace(r#""period":30"#, r#""period":0"#),
+            json.replace(r#""output_len":8"#, r#""output_len":3"#),
+            json.replace(r#""compatibility":"default""#, r#""compatibility":"other""#),
+        ];
+        for json in invalid.iter() {
+            assert!(serde_json::from_str::<TOTP>(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_account_identifier() {
+        let key = "12345678901234567890";
+        let totp = totp_at_time!(1111111080, key).finalize().unwrap();
+        assert_eq!(totp.account_identifier(), None);
+        assert!(format!("{:?}", totp).contains("account_identifier: None"));
+
+        let totp = totp_at_time!(1111111080, key)
+            .output_len(8)
+            .account_identifier("alice@example.com")
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.account_identifier(), Some("alice@example.com"));
+        assert_eq!(totp.generate(), "07081804");
+        assert_eq!(totp.clone().account_identifier(), Some("alice@example.com"));
+        let debug = format!("{:?}", totp);
+        #[cfg(feature = "pii-logging")]
+        assert!(debug.contains(r#"account_identifier: Some("alice@example.com")"#));
+        #[cfg(not(feature = "pii-logging"))]
+        assert!(!debug.contains("alice"));
+
+        #[cfg(feature = "oath-uri")]
+        {
+            let uri = totp.key_uri_format("Provider1", "").finalize();
+            assert!(uri.starts_with("otpauth://totp/Provider1:alice@example.com?"));
+            let uri = totp.key_uri_format("Provider1", "bob").finalize();
+            assert!(uri.starts_with("otpauth://totp/Provider1:bob?"));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_json() {
+        let json = TOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .hash_function(HashFunction::Sha256)
+            .period(60)
+            .issuer("Provider1")
+            .account_identifier("alice@example.com")
+            .to_json()
+            .unwrap();
+        assert_eq!(
+            json,
+            r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","algorithm":"SHA256","digits":6,"period":60,"issuer":"Provider1","account":"alice@example.com"}"#
+        );
+
+        let mut builder = TOTPBuilder::from_json(&json).unwrap();
+        assert_eq!(builder.to_json().unwrap(), json);
+        let totp = builder.timestamp(1111111080).finalize().unwrap();
+        let expected = TOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .hash_function(HashFunction::Sha256)
+            .period(60)
+            .timestamp(1111111080)
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.generate(), expected.generate());
+        assert_eq!(totp.issuer(), Some("Provider1"));
+        assert_eq!(totp.account_identifier(), Some("alice@example.com"));
+
+        let builder = TOTPBuilder::from_json(r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"}"#);
+        assert_eq!(
+            builder.unwrap().to_json().unwrap(),
+            r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","algorithm":"SHA1","digits":6,"period":30}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_json_errors() {
+        let cases = [
+            ("", ErrorCode::InvalidJson),
+            ("{}", ErrorCode::InvalidJson),
+            (r#"{"secret":42}"#, ErrorCode::InvalidJson),
+            (
+                r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","unknown":1}"#,
+                ErrorCode::InvalidJson,
+            ),
+            (r#"{"secret":"!!!"}"#, ErrorCode::InvalidKey),
+            (
+                r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","algorithm":"MD5"}"#,
+                ErrorCode::UnsupportedAlgorithm,
+            ),
+            (
+                r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","period":0}"#,
+                ErrorCode::InvalidPeriod,
+            ),
+            (
+                r#"{"secret":"GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ","digits":2}"#,
+                ErrorCode::CodeTooSmall,
+            ),
+        ];
+        for (json, error) in cases.iter() {
+            assert_eq!(TOTPBuilder::from_json(json).err(), Some(*error), "{}", json);
+        }
+
+        assert_eq!(
+            TOTPBuilder::new().to_json().err(),
+            Some(ErrorCode::InvalidKey)
+        );
+        let res = TOTPBuilder::new()
+            .ascii_key("12345678901234567890")
+            .output_base("0123456789abcdef")
+            .to_json();
+        assert_eq!(res.err(), Some(ErrorCode::IncompatibleOption));
+    }
+
+    #[test]
+    fn test_is_valid_at_near_initial_time() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(8)
+            .tolerance(1)
+            .finalize()
+            .unwrap();
+        assert!(!totp.is_valid_at("12345678", 10));
+        assert!(totp.is_valid_at(&totp.generate_at(10).unwrap(), 10));
+        assert!(totp.is_valid_at(&totp.generate_at(40).unwrap(), 10));
+        assert!(totp.is_valid_at(&totp.generate_at(10).unwrap(), 40));
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(8)
+            .negative_tolerance(100)
+            .finalize()
+            .unwrap();
+        let code = totp.generate_at(10).unwrap();
+        assert!(totp.is_valid_at(&code, 35));
+        assert!(totp.is_valid_at(&code, 65));
+    }
+
+    #[test]
+    fn test_is_valid_at_output_base() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_base("0123456789ABCDEF")
+            .finalize()
+            .unwrap();
+        let code = totp.generate_at(1111111109).unwrap();
+        assert_eq!(code, "10F84C");
+        assert!(totp.is_valid_at(&code, 1111111109));
+        assert!(!totp.is_valid_at("10F84D", 1111111109));
+    }
+
+    #[test]
+    fn test_time_based_counter() {
+        let key = "12345678901234567890";
+        let mut builder = TOTPBuilder::new();
+        builder.ascii_key(key);
+        for timestamp in [59, 1111111109, 1234567890, 2000000000, 20000000000].iter() {
+            let totp = builder.finalize().unwrap();
+            let expected = totp.generate_at(*timestamp as i64).unwrap();
+            let counter = builder.time_based_counter(*timestamp);
+            assert_eq!(counter, *timestamp / 30);
+            let hotp = HOTPBuilder::new()
+                .ascii_key(key)
+                .counter(counter)
+                .finalize()
+                .unwrap();
+            assert_eq!(hotp.generate(), expected);
+        }
+
+        builder.initial_time(100).epoch_offset_seconds(-10);
+        assert_eq!(builder.time_based_counter(109), u64::max_value());
+        assert_eq!(builder.time_based_counter(110), 0);
+        assert_eq!(builder.time_based_counter(170), 2);
+
+        let mut builder = TOTPBuilder::new();
+        builder.period_ms(1500).use_milliseconds(true);
+        assert_eq!(builder.time_based_counter(4499), 2);
+        assert_eq!(builder.time_based_counter(4500), 3);
+        builder.use_milliseconds(false);
+        assert_eq!(builder.time_based_counter(4500), u64::max_value());
+    }
+
+    #[test]
+    fn test_window_codes() {
+        let key = "12345678901234567890";
+        let totp = totp_at_time!(1111111090, key).finalize().unwrap();
+        let codes = totp.window_codes(0).unwrap();
+        assert_eq!(codes, vec![totp.generate()]);
+
+        let codes = totp.window_codes(2).unwrap();
+        assert_eq!(codes.len(), 5);
+        assert_eq!(codes[1], totp.generate_previous().unwrap());
+        assert_eq!(codes[2], totp.generate());
+        assert_eq!(codes[3], totp.generate_next().unwrap());
+        assert_eq!(codes[4], totp.generate_at(1111111150).unwrap());
+
+        let totp = totp_at_time!(45, key).finalize().unwrap();
+        assert_eq!(totp.window_codes(1).unwrap().len(), 3);
+        assert_eq!(
+            totp.window_codes(2).err(),
+            Some(ErrorCode::TimestampOverflow)
+        );
+    }
+
+    #[test]
+    fn test_is_same_window() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .period(60)
+            .initial_time(30)
+            .finalize()
+            .unwrap();
+        assert!(totp.is_same_window(30, 89));
+        assert!(totp.is_same_window(89, 30));
+        assert!(totp.is_same_window(90, 90));
+        assert!(!totp.is_same_window(89, 90));
+        assert!(!totp.is_same_window(0, 29));
+        assert!(!totp.is_same_window(29, 30));
+        assert!(totp.is_same_window(u64::max_value(), u64::max_value() - 1));
+    }
+
+    #[test]
+    fn test_verify_returning_counter() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = totp_at_time!(1111111090, &key_ascii)
+            .output_len(8)
+            .tolerance(1)
+            .finalize()
+            .unwrap();
+        let examples = [
+            (1111111079, 37037035),
+            (1111111109, 37037036),
+            (1111111139, 37037037),
+        ];
+        for &(timestamp, counter) in examples.iter() {
+            let code = totp.generate_at(timestamp).unwrap();
+            assert_eq!(totp.verify_returning_counter(&code), Ok(counter));
+        }
+        let code = totp.generate_at(1111111169).unwrap();
+        assert_eq!(
+            totp.verify_returning_counter(&code),
+            Err(ErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            totp.verify_returning_counter(""),
+            Err(ErrorCode::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_verify_and_return_skew() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = totp_at_time!(1111111090, &key_ascii)
+            .output_len(8)
+            .positive_tolerance(1)
+            .negative_tolerance(2)
+            .finalize()
+            .unwrap();
+        let examples = [
+            (1111111049, -2),
+            (1111111079, -1),
+            (1111111109, 0),
+            (1111111139, 1),
+        ];
+        for &(timestamp, skew) in examples.iter() {
+            let code = totp.generate_at(timestamp).unwrap();
+            assert_eq!(totp.verify_and_return_skew(&code), Ok(skew));
+        }
+        for &timestamp in [1111111019, 1111111169].iter() {
+            let code = totp.generate_at(timestamp).unwrap();
+            assert_eq!(
+                totp.verify_and_return_skew(&code),
+                Err(ErrorCode::InvalidKey)
+            );
+        }
+        assert_eq!(totp.verify_and_return_skew(""), Err(ErrorCode::InvalidKey));
+
+        let totp = totp_at_time!(1111111080, &key_ascii)
+            .output_len(8)
+            .tolerance(200)
+            .finalize()
+            .unwrap();
+        let code = totp.generate_at(1111111080 - 30 * 127).unwrap();
+        assert_eq!(totp.verify_and_return_skew(&code), Ok(-127));
+        let code = totp.generate_at(1111111080 + 30 * 128).unwrap();
+        assert_eq!(
+            totp.verify_and_return_skew(&code),
+            Err(ErrorCode::RangeTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_for_algorithm() {
+        let key_ascii = "12345678901234567890123456789012".to_owned();
+        for algo in ["totp-sha256-8-30", "TOTP-SHA-256-8-30", "totp-Sha_256-8-30"].iter() {
+            let totp = TOTPBuilder::for_algorithm(algo)
+                .unwrap()
+                .ascii_key(&key_ascii)
+                .finalize()
+                .unwrap();
+            assert!(totp.hash_function == HashFunction::Sha256);
+            assert_eq!(totp.output_len, 8);
+            assert_eq!(totp.period, 30);
+            assert_eq!(totp.generate_at(1111111109).unwrap(), "68084774");
+        }
+
+        let totp = TOTPBuilder::for_algorithm("totp-sha3-512-6-60")
+            .unwrap()
+            .ascii_key(&key_ascii)
+            .finalize()
+            .unwrap();
+        assert!(totp.hash_function == HashFunction::Sha3_512);
+        assert_eq!(totp.output_len, 6);
+        assert_eq!(totp.period, 60);
+
+        let invalid = [
+            "",
+            "totp",
+            "totp-sha256-8",
+            "hotp-sha256-8-30",
+            "totp-md5-8-30",
+            "totp--8-30",
+            "totp-sha256-eight-30",
+            "totp-sha256-8-",
+            "totp-sha256-8--30",
+        ];
+        for algo in invalid.iter() {
+            assert_eq!(
+                TOTPBuilder::for_algorithm(algo).err(),
+                Some(ErrorCode::UnsupportedAlgorithm)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn test_datetime() {
+        use chrono::TimeZone;
+
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(8)
+            .finalize()
+            .unwrap();
+        let examples = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1234567890, "89005924"),
+            (20000000000, "65353130"),
+        ];
+        for &(timestamp, code) in examples.iter() {
+            let dt = chrono::Utc.timestamp_opt(timestamp, 0).unwrap();
+            assert_eq!(totp.generate_at_datetime(dt).unwrap(), code);
+            assert_eq!(totp.verify_at_datetime(code, dt), Ok(true));
+            let next = chrono::Utc.timestamp_opt(timestamp + 30, 0).unwrap();
+            assert_eq!(totp.verify_at_datetime(code, next), Ok(false));
+        }
+
+        let dt = chrono::Utc.timestamp_opt(-1, 0).unwrap();
+        assert_eq!(
+            totp.generate_at_datetime(dt),
+            Err(ErrorCode::TimestampOverflow)
+        );
+        assert_eq!(
+            totp.verify_at_datetime("94287082", dt),
+            Err(ErrorCode::TimestampOverflow)
+        );
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(8)
+            .use_milliseconds(true)
+            .period_ms(500)
+            .finalize()
+            .unwrap();
+        let dt = chrono::Utc.timestamp_millis_opt(1111111109250).unwrap();
+        assert_eq!(
+            totp.generate_at_datetime(dt).unwrap(),
+            totp.generate_at(1111111109250).unwrap()
+        );
+        let dt = chrono::Utc.timestamp_millis_opt(1111111109499).unwrap();
+        assert_eq!(
+            totp.verify_at_datetime(&totp.generate_at(1111111109000).unwrap(), dt),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_generate_at_system_time() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .output_len(8)
+            .finalize()
+            .unwrap();
+        let examples = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1234567890, "89005924"),
+            (20000000000, "65353130"),
         ];
-        for &(timestamp, hash_function, ref_code) in examples.iter() {
-            let code = TOTPBuilder::new()
-                .hex_key(&key_hex)
-                .timestamp(timestamp)
-                .output_len(8)
-                .hash_function(hash_function)
-                .finalize()
-                .unwrap()
-                .generate();
-            assert_eq!(code.len(), 8);
-            assert_eq!(code, ref_code);
+        for &(timestamp, code) in examples.iter() {
+            let t = SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp);
+            assert_eq!(totp.generate_at_system_time(t).unwrap(), code);
         }
+        let now = SystemTime::now();
+        assert!(totp.is_valid(&totp.generate_at_system_time(now).unwrap()));
+        let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(
+            totp.generate_at_system_time(before_epoch),
+            Err(ErrorCode::ClockError)
+        );
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .initial_time(100)
+            .finalize()
+            .unwrap();
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(99);
+        assert_eq!(
+            totp.generate_at_system_time(t),
+            Err(ErrorCode::TimestampOverflow)
+        );
+
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .use_milliseconds(true)
+            .period_ms(500)
+            .finalize()
+            .unwrap();
+        let t = SystemTime::UNIX_EPOCH + Duration::from_millis(1111111109250);
+        assert_eq!(
+            totp.generate_at_system_time(t).unwrap(),
+            totp.generate_at(1111111109250).unwrap()
+        );
     }
 
     #[test]
-    fn test_rfc6238_examples_sha512() {
-        let key_hex = "31323334353637383930313233343536373839303132333435363738393031323334353637383930313233343536373839303132333435363738393031323334".to_owned();
+    #[cfg(feature = "debug")]
+    fn test_debug_info() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let debug_info = totp_at_time!(45, &key_ascii)
+            .output_len(8)
+            .finalize()
+            .unwrap()
+            .debug_info()
+            .unwrap();
+        assert_eq!(
+            debug_info,
+            TOTPDebugInfo {
+                counter: 1,
+                hmac: "75a48a19d4cbe100644e8ac1397eea747a2d33ab".to_string(),
+                offset: 11,
+                truncated_value: 0x4139_7eea,
+                code: "94287082".to_string(),
+            }
+        );
+
+        // RFC 6238 appendix B, SHA-1 test vectors.
         let examples = [
-            (59, HashFunction::Sha512, "90693936"),
-            (1111111109, HashFunction::Sha512, "25091201"),
-            (1111111111, HashFunction::Sha512, "99943326"),
-            (1234567890, HashFunction::Sha512, "93441116"),
-            (2000000000, HashFunction::Sha512, "38618901"),
-            (20000000000, HashFunction::Sha512, "47863826"),
+            (1111111109, 0x0235_23ec, "07081804"),
+            (1111111111, 0x0235_23ed, "14050471"),
+            (1234567890, 0x0273_ef07, "89005924"),
+            (2000000000, 0x03f9_40aa, "69279037"),
+            (20000000000, 0x27bc_86aa, "65353130"),
         ];
-        for &(timestamp, hash_function, ref_code) in examples.iter() {
-            let code = TOTPBuilder::new()
-                .hex_key(&key_hex)
-                .timestamp(timestamp)
+        for &(timestamp, counter, code) in examples.iter() {
+            let debug_info = TOTPBuilder::new()
+                .ascii_key(&key_ascii)
                 .output_len(8)
-                .hash_function(hash_function)
                 .finalize()
                 .unwrap()
-                .generate();
-            assert_eq!(code.len(), 8);
-            assert_eq!(code, ref_code);
+                .debug_info_at(timestamp)
+                .unwrap();
+            assert_eq!(debug_info.counter, counter);
+            assert_eq!(debug_info.code, code);
+            assert_eq!(debug_info.hmac.len(), 40);
+            let last_byte = u8::from_str_radix(&debug_info.hmac[38..], 16).unwrap();
+            assert_eq!(debug_info.offset, (last_byte & 0xf) as usize);
+            let start = debug_info.offset * 2;
+            let extracted = u32::from_str_radix(&debug_info.hmac[start..start + 8], 16).unwrap();
+            assert_eq!(debug_info.truncated_value, extracted & 0x7fff_ffff);
+            assert_eq!(
+                format!("{:08}", debug_info.truncated_value % 100_000_000),
+                code
+            );
         }
     }
 
     #[test]
-    fn test_valid_code() {
+    fn test_generate_at() {
         let key_ascii = "12345678901234567890".to_owned();
-        let user_code = "94287082".to_owned();
-        let valid = TOTPBuilder::new()
+        let totp = TOTPBuilder::new()
             .ascii_key(&key_ascii)
-            .timestamp(59)
             .output_len(8)
+            .tolerance(1)
             .finalize()
-            .unwrap()
-            .is_valid(&user_code);
-        assert_eq!(valid, true);
+            .unwrap();
+        assert_eq!(totp.generate_at(1111111109).unwrap(), "07081804");
+        assert_eq!(totp.generate_at(1111111111).unwrap(), "14050471");
+        assert!(totp.is_valid_at("07081804", 1111111109));
+        assert!(totp.is_valid_at("07081804", 1111111111));
+        assert!(!totp.is_valid_at("07081804", 1111111200));
+        match totp.generate_at(-1) {
+            Err(ErrorCode::TimestampOverflow) => {}
+            _ => panic!("a code has been generated for a negative timestamp"),
+        }
+        assert!(!totp.is_valid_at("07081804", -1));
     }
 
     #[test]
-    fn test_tolerance() {
+    fn test_generate_previous_next() {
         let key_ascii = "12345678901234567890".to_owned();
-        let examples = [
-            (1234567890, 0, "590587", false), // +1
-            (1234567890, 1, "590587", true),  // +1
-            (1234567890, 1, "240500", false), // +2
-            (1234567890, 2, "240500", true),  // +2
-            (1234567890, 0, "980357", false), // -1
-            (1234567890, 1, "980357", true),  // -1
-            (1234567890, 1, "186057", false), // -2
-            (1234567890, 2, "186057", true),  // -2
-        ];
-        for &(timestamp, tolerance, user_code, validity) in examples.iter() {
-            let valid = TOTPBuilder::new()
-                .ascii_key(&key_ascii)
-                .timestamp(timestamp)
-                .tolerance(tolerance)
-                .finalize()
-                .unwrap()
-                .is_valid(&user_code.to_owned());
-            assert_eq!(valid, validity);
+        let totp = totp_at_time!(1111111090, &key_ascii)
+            .output_len(8)
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.generate(), "07081804");
+        assert_eq!(totp.generate_next().unwrap(), "14050471");
+        assert_ne!(totp.generate_previous().unwrap(), "07081804");
+
+        let totp = totp_at_time!(1111111111, &key_ascii)
+            .output_len(8)
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.generate_previous().unwrap(), "07081804");
+    }
+
+    #[test]
+    fn test_clock_error() {
+        let err = SystemTime::UNIX_EPOCH
+            .duration_since(SystemTime::now())
+            .unwrap_err();
+        match ErrorCode::from(err) {
+            ErrorCode::ClockError => {}
+            _ => panic!("invalid error code"),
         }
     }
 
     #[test]
-    fn test_positive_tolerance() {
+    fn test_unsupported_algorithm() {
+        fn get_hash_function(name: &str) -> Result<HashFunction, ErrorCode> {
+            Ok(HashFunction::try_from(name)?)
+        }
+        assert!(get_hash_function("SHA-512").unwrap() == HashFunction::Sha512);
+        assert_eq!(
+            get_hash_function("md5").err(),
+            Some(ErrorCode::UnsupportedAlgorithm)
+        );
+    }
+
+    #[test]
+    fn test_before_initial_time() {
         let key_ascii = "12345678901234567890".to_owned();
-        let examples = [
-            (1234567890, 0, "590587", false), // +1
-            (1234567890, 1, "590587", true),  // +1
-            (1234567890, 1, "240500", false), // +2
-            (1234567890, 2, "240500", true),  // +2
-            (1234567890, 0, "980357", false), // -1
-            (1234567890, 1, "980357", false), // -1
-            (1234567890, 1, "186057", false), // -2
-            (1234567890, 2, "186057", false), // -2
-        ];
-        for &(timestamp, tolerance, user_code, validity) in examples.iter() {
-            let valid = TOTPBuilder::new()
-                .ascii_key(&key_ascii)
-                .timestamp(timestamp)
-                .positive_tolerance(tolerance)
-                .finalize()
-                .unwrap()
-                .is_valid(&user_code.to_owned());
-            assert_eq!(valid, validity);
+        let totp = totp_at_time!(1111111090, &key_ascii)
+            .initial_time(1111111200)
+            .finalize()
+            .unwrap();
+        assert!(!totp.is_valid("081804"));
+        match totp.verify_multi(&["081804"]) {
+            Err(ErrorCode::TimestampOverflow) => {}
+            _ => panic!("a code has been checked before the initial time"),
         }
     }
 
     #[test]
-    fn test_negative_tolerance() {
+    fn test_generate_previous_overflow() {
         let key_ascii = "12345678901234567890".to_owned();
-        let examples = [
-            (1234567890, 0, "590587", false), // +1
-            (1234567890, 1, "590587", false), // +1
-            (1234567890, 1, "240500", false), // +2
-            (1234567890, 2, "240500", false), // +2
-            (1234567890, 0, "980357", false), // -1
-            (1234567890, 1, "980357", true),  // -1
-            (1234567890, 1, "186057", false), // -2
-            (1234567890, 2, "186057", true),  // -2
-        ];
-        for &(timestamp, tolerance, user_code, validity) in examples.iter() {
-            let valid = TOTPBuilder::new()
-                .ascii_key(&key_ascii)
-                .timestamp(timestamp)
-                .negative_tolerance(tolerance)
-                .finalize()
-                .unwrap()
-                .is_valid(&user_code.to_owned());
-            assert_eq!(valid, validity);
+        let totp = totp_at_time!(1111111109, &key_ascii)
+            .initial_time(1111111100)
+            .finalize()
+            .unwrap();
+        match totp.generate_previous() {
+            Err(ErrorCode::TimestampOverflow) => {}
+            _ => panic!("a code has been generated before the initial time"),
         }
+        assert!(totp.generate_next().is_ok());
     }
 
     #[test]
-    fn test_invalid_code() {
+    fn test_google_authenticator_compat() {
         let key_ascii = "12345678901234567890".to_owned();
-        let user_code = "12345678".to_owned();
-        let valid = TOTPBuilder::new()
+        let totp = TOTPBuilder::new()
             .ascii_key(&key_ascii)
-            .timestamp(59)
+            .hash_function(HashFunction::Sha256)
             .output_len(8)
+            .period(60)
+            .google_authenticator_compat()
             .finalize()
-            .unwrap()
-            .is_valid(&user_code);
-        assert_eq!(valid, false);
+            .unwrap();
+        assert!(totp.hash_function == HashFunction::Sha1);
+        assert_eq!(totp.output_len, 6);
+        assert_eq!(totp.period, 30);
+
+        let gauth = || {
+            let mut builder = TOTPBuilder::new();
+            builder.ascii_key(&key_ascii).google_authenticator_compat();
+            builder
+        };
+        assert!(gauth().finalize().is_ok());
+        let invalids = [
+            gauth().hash_function(HashFunction::Sha512).finalize(),
+            gauth().output_len(8).finalize(),
+            gauth().period(60).finalize(),
+            gauth().initial_time(42).finalize(),
+            gauth().output_base("0123456789abcdef").finalize(),
+        ];
+        for res in invalids.iter() {
+            match res {
+                Err(ErrorCode::IncompatibleOption) => {}
+                _ => panic!("invalid configuration accepted"),
+            }
+        }
     }
 
     #[test]
-    fn test_bad_code() {
+    #[cfg(feature = "oath-uri")]
+    fn test_key_uri_format_google_authenticator_compat() {
         let key_ascii = "12345678901234567890".to_owned();
-        let user_code = "!@#$%^&*".to_owned();
-        let valid = TOTPBuilder::new()
+        let totp = TOTPBuilder::new()
             .ascii_key(&key_ascii)
-            .timestamp(59)
-            .output_len(8)
+            .google_authenticator_compat()
             .finalize()
-            .unwrap()
-            .is_valid(&user_code);
-        assert_eq!(valid, false);
+            .unwrap();
+
+        let uri = totp
+            .key_uri_format("Provider1", "alice@example.com")
+            .finalize();
+
+        assert_eq!(
+            uri,
+            "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1&algorithm=SHA1&digits=6&period=30"
+        );
     }
 
     #[test]
-    fn test_empty_code() {
+    fn test_microsoft_authenticator_compat() {
         let key_ascii = "12345678901234567890".to_owned();
-        let user_code = "".to_owned();
-        let valid = TOTPBuilder::new()
-            .ascii_key(&key_ascii)
-            .timestamp(59)
-            .output_len(8)
+        let msauth = |hash_function, output_len, period| {
+            TOTPBuilder::new()
+                .ascii_key(&key_ascii)
+                .microsoft_authenticator_compat()
+                .hash_function(hash_function)
+                .output_len(output_len)
+                .period(period)
+                .timestamp(1234567890)
+                .finalize()
+        };
+        for hash_function in [
+            HashFunction::Sha1,
+            HashFunction::Sha256,
+            HashFunction::Sha512,
+        ]
+        .iter()
+        {
+            for output_len in [6, 8].iter() {
+                for period in [30, 60].iter() {
+                    let totp = msauth(*hash_function, *output_len, *period).unwrap();
+                    let reference = TOTPBuilder::new()
+                        .ascii_key(&key_ascii)
+                        .hash_function(*hash_function)
+                        .output_len(*output_len)
+                        .period(*period)
+                        .timestamp(1234567890)
+                        .finalize()
+                        .unwrap();
+                    assert_eq!(totp.key.len(), 20);
+                    assert_eq!(totp.generate(), reference.generate());
+                }
+            }
+        }
+        let invalids = [
+            msauth(HashFunction::Sha384, 6, 30),
+            msauth(HashFunction::Sha1, 7, 30),
+            msauth(HashFunction::Sha1, 6, 42),
+        ];
+        for res in invalids.iter() {
+            match res {
+                Err(ErrorCode::IncompatibleOption) => {}
+                _ => panic!("invalid configuration accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_microsoft_authenticator_compat_key_padding() {
+        let key = [42u8; 18];
+        let totp = TOTPBuilder::new()
+            .key(&key)
+            .microsoft_authenticator_compat()
+            .timestamp(1234567890)
             .finalize()
-            .unwrap()
-            .is_valid(&user_code);
-        assert_eq!(valid, false);
+            .unwrap();
+        let reference = TOTPBuilder::new()
+            .key(&key)
+            .timestamp(1234567890)
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.key.len(), 20);
+        assert_eq!(&totp.key[..18], &key[..]);
+        assert_eq!(&totp.key[18..], &[0, 0]);
+        assert_eq!(totp.generate(), reference.generate());
+
+        let key = [42u8; 66];
+        let totp = TOTPBuilder::new()
+            .key(&key)
+            .microsoft_authenticator_compat()
+            .finalize()
+            .unwrap();
+        assert_eq!(totp.key.len(), 66);
     }
 
     #[test]
     #[cfg(feature = "oath-uri")]
-    fn test_key_uri_format() {
-        let key_ascii = "12345678901234567890".to_owned();
-        let totp = TOTPBuilder::new().ascii_key(&key_ascii).finalize().unwrap();
+    fn test_key_uri_format_microsoft_authenticator_compat() {
+        let key = b"1234567890123456".to_vec();
+        let totp = TOTPBuilder::new()
+            .key(&key)
+            .microsoft_authenticator_compat()
+            .hash_function(HashFunction::Sha256)
+            .output_len(8)
+            .period(60)
+            .finalize()
+            .unwrap();
 
         let uri = totp
             .key_uri_format("Provider1", "alice@example.com")
             .finalize();
 
+        let uri = url::Url::parse(&uri).unwrap();
+        assert_eq!(uri.scheme(), "otpauth");
+        assert_eq!(uri.host_str(), Some("totp"));
+        let params: std::collections::HashMap<_, _> = uri.query_pairs().into_owned().collect();
+        assert_eq!(params.len(), 5);
+        assert_eq!(params["issuer"], "Provider1");
+        assert_eq!(params["algorithm"], "SHA256");
+        assert_eq!(params["digits"], "8");
+        assert_eq!(params["period"], "60");
+        let secret = &params["secret"];
+        assert_eq!(secret.len() % 8, 0);
+        let mut padded_key = key.clone();
+        padded_key.extend_from_slice(&[0, 0, 0, 0]);
         assert_eq!(
-            uri,
-            "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1"
+            base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret).unwrap(),
+            padded_key
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "oath-uri")]
+    fn test_key_uri_image() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let totp = TOTPBuilder::new()
+            .ascii_key(&key_ascii)
+            .image_url("https://example.com/logo.png?size=64&theme=dark")
+            .finalize()
+            .unwrap();
+        let uri = totp
+            .key_uri_format("Provider1", "alice@example.com")
+            .finalize();
+        let uri = url::Url::parse(&uri).unwrap();
+        let params: std::collections::HashMap<_, _> = uri.query_pairs().into_owned().collect();
+        assert_eq!(
+            params["image"],
+            "https://example.com/logo.png?size=64&theme=dark"
         );
+        assert_eq!(params.len(), 3);
+
+        let invalid = [
+            "",
+            "logo.png",
+            "http://example.com/logo.png",
+            "javascript:alert(1)",
+            "data:image/png;base64,AAAA",
+            "otpauth://totp/Provider1",
+        ];
+        for url in invalid.iter() {
+            assert_eq!(
+                TOTPBuilder::new()
+                    .ascii_key(&key_ascii)
+                    .image_url(url)
+                    .finalize()
+                    .err(),
+                Some(ErrorCode::InvalidUri)
+            );
+        }
     }
 
     #[test]
@@ -980,4 +3591,21 @@ mod tests {
             .finalize();
         assert_eq!(uri, "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1&base=%C3%A8_%C3%A9%C3%B6%E2%82%AC%E2%80%A6%C3%B7%E2%80%94%E2%98%BA");
     }
+
+    #[test]
+    #[cfg(feature = "oath-uri")]
+    fn test_key_uri_format_output_base_encoded() {
+        let key_ascii = "12345678901234567890".to_owned();
+        let base = "0123456789&=?%#";
+        let totp = TOTPBuilder::new()
+            .output_base(&base)
+            .ascii_key(&key_ascii)
+            .finalize()
+            .unwrap();
+
+        let uri = totp
+            .key_uri_format("Provider1", "alice@example.com")
+            .finalize();
+        assert_eq!(uri, "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1&base=0123456789%26%3D%3F%25%23");
+    }
 }
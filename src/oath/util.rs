@@ -0,0 +1,519 @@
+//! Encoding utilities used to handle the shared secrets, a constant time comparison, validation of
+//! the output bases and, with the `oath-internals` feature, the HMAC functions used to generate the
+//! codes.
+//!
+//! Since those functions mostly handle secret keys, they are implemented in constant time with
+//! respect to the processed data. However, the input format (e.g. its length) is considered public
+//! and therefore is not protected.
+
+use super::{ErrorCode, ValidationErrors};
+#[cfg(feature = "oath-internals")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "oath-internals")]
+use sha1::Sha1;
+#[cfg(feature = "oath-internals")]
+use sha2::{Sha256, Sha512};
+use subtle::{
+    Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater, ConstantTimeLess,
+};
+
+/// Returns the value of an hexadecimal digit and a mask set to `-1` if the digit is valid or to
+/// `0` otherwise. No branch depend on the value of the digit.
+fn ct_hex_digit(c: u8) -> (i32, i32) {
+    let c = i32::from(c);
+    let digit = c - 0x30;
+    let alpha = (c | 0x20) - 0x61;
+    let digit_ok = !((digit | (9 - digit)) >> 31);
+    let alpha_ok = !((alpha | (5 - alpha)) >> 31);
+    let value = (digit & digit_ok) | ((alpha + 10) & alpha_ok);
+    (value, digit_ok | alpha_ok)
+}
+
+/// Decodes an hexadecimal string in constant time with respect to the encoded data.
+///
+/// Both lower and upper case digits are accepted. An invalid character or an odd number of digits
+/// results in an `InvalidKey` error.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::util::ct_hex_decode;
+///
+/// assert_eq!(ct_hex_decode("00ff7Fa0").unwrap(), vec![0x00, 0xff, 0x7f, 0xa0]);
+/// assert!(ct_hex_decode("0g").is_err());
+/// ```
+pub fn ct_hex_decode(s: &str) -> Result<Vec<u8>, ErrorCode> {
+    let s = s.as_bytes();
+    if s.len() % 2 == 1 {
+        return Err(ErrorCode::InvalidKey);
+    }
+    let mut valid = -1;
+    let mut out = Vec::with_capacity(s.len() / 2);
+    for pair in s.chunks(2) {
+        let (high, high_ok) = ct_hex_digit(pair[0]);
+        let (low, low_ok) = ct_hex_digit(pair[1]);
+        valid &= high_ok & low_ok;
+        out.push(((high << 4) | low) as u8);
+    }
+    if valid == 0 {
+        return Err(ErrorCode::InvalidKey);
+    }
+    Ok(out)
+}
+
+/// Returns whether or not `c` is in the `[lo, hi]` range without branching on `c`.
+fn ct_in_range(c: u8, lo: u8, hi: u8) -> Choice {
+    !c.ct_lt(&lo) & !c.ct_gt(&hi)
+}
+
+/// Returns the value of a base32 digit (RFC 4648 alphabet, case insensitive) and whether or not
+/// the digit is valid. No branch depend on the value of the digit.
+fn ct_base32_digit(c: u8) -> (u8, Choice) {
+    let lower = c | 0x20;
+    let is_alpha = ct_in_range(lower, b'a', b'z');
+    let is_digit = ct_in_range(c, b'2', b'7');
+    let mut value = u8::conditional_select(&0, &lower.wrapping_sub(b'a'), is_alpha);
+    value.conditional_assign(&c.wrapping_sub(b'2').wrapping_add(26), is_digit);
+    (value, is_alpha | is_digit)
+}
+
+/// Returns the upper case base32 digit (RFC 4648 alphabet) of a 5-bit value without branching on
+/// the value.
+fn ct_base32_char(value: u8) -> u8 {
+    let is_digit = !value.ct_lt(&26);
+    u8::conditional_select(
+        &b'A'.wrapping_add(value),
+        &b'2'.wrapping_add(value).wrapping_sub(26),
+        is_digit,
+    )
+}
+
+/// Encodes bytes as a base32 string (RFC 4648 alphabet, upper case, without padding) in constant
+/// time with respect to the data. The result can be used with `base32_key`.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::util::base32_encode;
+///
+/// assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+/// ```
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 8 / 5 + 1);
+    for chunk in bytes.chunks(5) {
+        let mut buff: u64 = 0;
+        for b in chunk.iter().chain(std::iter::repeat(&0)).take(5) {
+            buff = (buff << 8) | u64::from(*b);
+        }
+        let nb_chars = (chunk.len() * 8 - 1) / 5 + 1;
+        for i in 0..nb_chars {
+            let value = ((buff >> (35 - 5 * i)) & 0x1f) as u8;
+            out.push(char::from(ct_base32_char(value)));
+        }
+    }
+    out
+}
+
+/// Decodes a base32 string (RFC 4648 alphabet) in constant time with respect to the encoded data.
+///
+/// Both lower and upper case digits are accepted and the trailing padding, if any, is ignored. An
+/// invalid character results in an `InvalidKey` error.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::util::base32_decode;
+///
+/// assert_eq!(base32_decode("MZXW6YTBOI").unwrap(), b"foobar".to_vec());
+/// assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar".to_vec());
+/// assert!(base32_decode("MZXW1").is_err());
+/// ```
+pub fn base32_decode(s: &str) -> Result<Vec<u8>, ErrorCode> {
+    let s = s.trim_end_matches('=').as_bytes();
+    let mut valid = Choice::from(1);
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    for chunk in s.chunks(8) {
+        let mut buff: u64 = 0;
+        for c in chunk.iter().chain(std::iter::repeat(&b'A')).take(8) {
+            let (value, is_valid) = ct_base32_digit(*c);
+            valid &= is_valid;
+            buff = (buff << 5) | u64::from(value);
+        }
+        let nb_bytes = chunk.len() * 5 / 8;
+        out.extend_from_slice(&buff.to_be_bytes()[3..3 + nb_bytes]);
+    }
+    if !bool::from(valid) {
+        return Err(ErrorCode::InvalidKey);
+    }
+    Ok(out)
+}
+
+/// Returns whether or not both slices are equal, in time proportional to
+/// `max(a.len(), b.len())` regardless of their content. Slices of different lengths are never
+/// equal, but only their lengths, not their content, can be inferred from the time taken.
+///
+/// This can be used to check secrets such as session tokens or HMAC tags without depending on a
+/// constant time comparison crate.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::util::secure_compare;
+///
+/// assert!(secure_compare(b"secret token", b"secret token"));
+/// assert!(!secure_compare(b"secret token", b"secret tokem"));
+/// assert!(!secure_compare(b"secret token", b"secret"));
+/// ```
+pub fn secure_compare(a: &[u8], b: &[u8]) -> bool {
+    let mut equal = (a.len() as u64).ct_eq(&(b.len() as u64));
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        equal &= x.ct_eq(&y);
+    }
+    bool::from(equal)
+}
+
+/// Checks whether a string can be used as the output base of the codes and returns every issue
+/// found: `ErrorCode::InvalidBaseLen` if it has less than 2 characters and
+/// `ErrorCode::InvalidBase` if it is not valid UTF-8, contains a character twice or contains a
+/// whitespace or control character.
+///
+/// The builders' `finalize` method performs this check, so applications can use this function to
+/// validate a base before creating a builder.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::util::validate_output_base;
+/// use libreauth::oath::ErrorCode;
+///
+/// assert!(validate_output_base(b"0123456789abcdef").is_ok());
+/// assert_eq!(
+///     validate_output_base(b"a").unwrap_err().as_slice(),
+///     &[ErrorCode::InvalidBaseLen]
+/// );
+/// assert_eq!(
+///     validate_output_base(b"abca").unwrap_err().as_slice(),
+///     &[ErrorCode::InvalidBase]
+/// );
+/// ```
+pub fn validate_output_base(base: &[u8]) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    let chars: Option<Vec<char>> = std::str::from_utf8(base).ok().map(|s| s.chars().collect());
+    let len = chars
+        .as_ref()
+        .map(|c| c.len())
+        .unwrap_or_else(|| base.len());
+    if len < 2 {
+        errors |= ErrorCode::InvalidBaseLen;
+    }
+    match chars {
+        Some(chars) => {
+            if chars.iter().any(|c| c.is_whitespace() || c.is_control()) {
+                errors |= ErrorCode::InvalidBase;
+            }
+            if chars
+                .iter()
+                .enumerate()
+                .any(|(i, c)| chars[i + 1..].contains(c))
+            {
+                errors |= ErrorCode::InvalidBase;
+            }
+        }
+        None => errors |= ErrorCode::InvalidBase,
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns whether or not the key is empty, only contains null bytes or repeats a pattern no
+/// longer than a third of the key, rounded up, the last repetition being possibly truncated (e.g.
+/// `abcabcab`).
+pub(crate) fn is_weak_key(key: &[u8]) -> bool {
+    if key.iter().all(|b| *b == 0) {
+        return true;
+    }
+    if key.len() < 2 {
+        return false;
+    }
+    (1..=(key.len() - 1) / 3 + 1).any(|len| key.iter().zip(&key[len..]).all(|(a, b)| a == b))
+}
+
+#[cfg(feature = "oath-internals")]
+macro_rules! hmac_fixed {
+    ($hash: ty, $len: expr, $key: expr, $msg: expr) => {{
+        // HMAC accepts keys of any length.
+        let mut hmac = Hmac::<$hash>::new_varkey($key).unwrap();
+        hmac.input($msg);
+        let mut out = [0u8; $len];
+        out.copy_from_slice(&hmac.result().code());
+        out
+    }};
+}
+
+/// Computes the HMAC-SHA1 of a message, as used when generating HOTP and TOTP codes.
+///
+/// This function is only available with the `oath-internals` feature.
+///
+/// ## Examples
+/// ```
+/// use libreauth::oath::util::hmac_sha1;
+///
+/// let mac = hmac_sha1(b"12345678901234567890", &[0u8; 8]);
+/// assert_eq!(mac[..4], [0xcc, 0x93, 0xcf, 0x18]);
+/// ```
+#[cfg(feature = "oath-internals")]
+pub fn hmac_sha1(key: &[u8], msg: &[u8]) -> [u8; 20] {
+    hmac_fixed!(Sha1, 20, key, msg)
+}
+
+/// Computes the HMAC-SHA256 of a message, as used when generating HOTP and TOTP codes.
+///
+/// This function is only available with the `oath-internals` feature.
+#[cfg(feature = "oath-internals")]
+pub fn hmac_sha256(key: &[u8], msg: &[u8]) -> [u8; 32] {
+    hmac_fixed!(Sha256, 32, key, msg)
+}
+
+/// Computes the HMAC-SHA512 of a message, as used when generating HOTP and TOTP codes.
+///
+/// This function is only available with the `oath-internals` feature.
+#[cfg(feature = "oath-internals")]
+pub fn hmac_sha512(key: &[u8], msg: &[u8]) -> [u8; 64] {
+    hmac_fixed!(Sha512, 64, key, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        base32_decode, base32_encode, ct_hex_decode, is_weak_key, secure_compare,
+        validate_output_base,
+    };
+    #[cfg(feature = "oath-internals")]
+    use super::{hmac_sha1, hmac_sha256, hmac_sha512};
+    use crate::oath::ErrorCode;
+
+    #[test]
+    fn test_ct_hex_decode() {
+        let tests = [
+            ("", vec![]),
+            ("00", vec![0x00]),
+            ("0123456789", vec![0x01, 0x23, 0x45, 0x67, 0x89]),
+            ("abcdef", vec![0xab, 0xcd, 0xef]),
+            ("ABCDEF", vec![0xab, 0xcd, 0xef]),
+            ("fF0f", vec![0xff, 0x0f]),
+            (
+                "3132333435363738393031323334353637383930",
+                "12345678901234567890".as_bytes().to_vec(),
+            ),
+        ];
+        for (input, expected) in tests.iter() {
+            assert_eq!(&ct_hex_decode(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_ct_hex_decode_same_as_hex() {
+        for b in 0..=255u8 {
+            let s = format!("{:02x}{:02X}", b, b);
+            assert_eq!(ct_hex_decode(&s).unwrap(), hex::decode(&s).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_ct_hex_decode_invalid() {
+        let tests = [
+            "0", "abc", "0g", "g0", "/0", ":0", "@0", "`0", "G0", " 0", "é0", "0-",
+        ];
+        for input in tests.iter() {
+            assert!(ct_hex_decode(input).is_err(), "{} has been decoded", input);
+        }
+        for c in 0..=255u8 {
+            if !(c as char).is_ascii_hexdigit() {
+                let s = [b'0', c];
+                let s = String::from_utf8_lossy(&s);
+                assert!(ct_hex_decode(&s).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_base32_decode() {
+        let tests = [
+            ("", vec![]),
+            ("MY", b"f".to_vec()),
+            ("MZXQ", b"fo".to_vec()),
+            ("MZXW6", b"foo".to_vec()),
+            ("MZXW6YQ", b"foob".to_vec()),
+            ("MZXW6YTB", b"fooba".to_vec()),
+            ("MZXW6YTBOI", b"foobar".to_vec()),
+            ("MZXW6YTBOI======", b"foobar".to_vec()),
+            ("mzxw6ytboi", b"foobar".to_vec()),
+            (
+                "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+                b"12345678901234567890".to_vec(),
+            ),
+        ];
+        for (input, expected) in tests.iter() {
+            assert_eq!(&base32_decode(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_base32_decode_same_as_base32() {
+        let alphabet = base32::Alphabet::RFC4648 { padding: false };
+        for len in 0..42 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + len) as u8).collect();
+            let encoded = base32::encode(alphabet, &data);
+            assert_eq!(base32_decode(&encoded).unwrap(), data);
+            assert_eq!(
+                base32_decode(&encoded).unwrap(),
+                base32::decode(alphabet, &encoded).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_secure_compare() {
+        let equal: [(&[u8], &[u8]); 3] = [(b"", b""), (b"\0", b"\0"), (b"abcdef", b"abcdef")];
+        for (a, b) in equal.iter() {
+            assert!(secure_compare(a, b), "{:?} != {:?}", a, b);
+        }
+
+        let different: [(&[u8], &[u8]); 6] = [
+            (b"", b"\0"),
+            (b"\0", b""),
+            (b"abcdef", b"abcdeg"),
+            (b"abcdef", b"bbcdef"),
+            (b"abcdef", b"abcde"),
+            (b"abcdef\0", b"abcdef"),
+        ];
+        for (a, b) in different.iter() {
+            assert!(!secure_compare(a, b), "{:?} == {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_validate_output_base() {
+        let valid: [&[u8]; 5] = [
+            b"01",
+            b"0123456789",
+            b"0123456789abcdef",
+            b"!#$%&*+-=?@^_~",
+            "è_éö€…÷—☺".as_bytes(),
+        ];
+        for base in valid.iter() {
+            assert!(validate_output_base(base).is_ok(), "{:?}", base);
+        }
+        assert!(validate_output_base(crate::oath::DEFAULT_OUTPUT_BASE).is_ok());
+
+        let invalid: [(&[u8], &[ErrorCode]); 8] = [
+            (b"", &[ErrorCode::InvalidBaseLen]),
+            (b"0", &[ErrorCode::InvalidBaseLen]),
+            (b"00", &[ErrorCode::InvalidBase]),
+            (b"0123456780", &[ErrorCode::InvalidBase]),
+            (b"0 1", &[ErrorCode::InvalidBase]),
+            (b"01\n", &[ErrorCode::InvalidBase]),
+            ("éé".as_bytes(), &[ErrorCode::InvalidBase]),
+            (
+                b"\xc3",
+                &[ErrorCode::InvalidBaseLen, ErrorCode::InvalidBase],
+            ),
+        ];
+        for (base, errors) in invalid.iter() {
+            let res = validate_output_base(base);
+            assert_eq!(res.unwrap_err().as_slice(), *errors, "{:?}", base);
+        }
+    }
+
+    #[test]
+    fn test_is_weak_key() {
+        let weak: [&[u8]; 9] = [
+            b"",
+            b"\0",
+            b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+            b"aaaaaaaaaaaaaaaaaaaa",
+            b"abababababababababab",
+            b"abcabcabcabcabcabcab",
+            b"abcabcab",
+            b"aa",
+            b"012345670123456701234567",
+        ];
+        for key in weak.iter() {
+            assert!(is_weak_key(key), "{:?} is not weak", key);
+        }
+        let strong: [&[u8]; 5] = [
+            b"a",
+            b"ab",
+            b"12345678901234567890",
+            b"abababababababababac",
+            b"0123456789abcdef0123456789abcdef",
+        ];
+        for key in strong.iter() {
+            assert!(!is_weak_key(key), "{:?} is weak", key);
+        }
+    }
+
+    #[test]
+    fn test_base32_encode() {
+        let tests = [
+            (b"".to_vec(), ""),
+            (b"f".to_vec(), "MY"),
+            (b"fo".to_vec(), "MZXQ"),
+            (b"foo".to_vec(), "MZXW6"),
+            (b"foob".to_vec(), "MZXW6YQ"),
+            (b"fooba".to_vec(), "MZXW6YTB"),
+            (b"foobar".to_vec(), "MZXW6YTBOI"),
+            (
+                b"12345678901234567890".to_vec(),
+                "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            ),
+        ];
+        for (input, expected) in tests.iter() {
+            assert_eq!(&base32_encode(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_base32_encode_same_as_base32() {
+        let alphabet = base32::Alphabet::RFC4648 { padding: false };
+        for len in 0..42 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + len) as u8).collect();
+            let encoded = base32_encode(&data);
+            assert_eq!(encoded, base32::encode(alphabet, &data));
+            assert_eq!(base32_decode(&encoded).unwrap(), data);
+        }
+        let data: Vec<u8> = (0..=255u8).collect();
+        assert_eq!(base32_encode(&data), base32::encode(alphabet, &data));
+    }
+
+    #[test]
+    fn test_base32_decode_invalid() {
+        let tests = [
+            "MZXW1", "MZXW8", "MZ=XW", "MZXW@", "MZXW[", "MZXW`", "MZXW{", "MZXWé",
+        ];
+        for input in tests.iter() {
+            assert!(base32_decode(input).is_err(), "{} has been decoded", input);
+        }
+    }
+
+    // RFC 4231 (and RFC 2202 for SHA-1), test case 2
+    #[test]
+    #[cfg(feature = "oath-internals")]
+    fn test_hmac() {
+        let key = b"Jefe";
+        let msg = b"what do ya want for nothing?";
+        assert_eq!(
+            hex::encode(hmac_sha1(key, msg)),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            hex::encode(hmac_sha256(key, msg)),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex::encode(&hmac_sha512(key, msg)[..]),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+}
@@ -8,6 +8,7 @@ use crate::{deref_ptr, deref_ptr_mut, get_slice, get_slice_mut, get_string};
 use libc;
 use std;
 use std::ffi::CStr;
+use zeroize::Zeroizing;
 
 /// [C binding]
 ///
@@ -214,7 +215,7 @@ pub unsafe extern "C" fn libreauth_pass_hash(
     dest_len: libc::size_t,
 ) -> ErrorCode {
     let c: &PassCfg = deref_ptr!(cfg, ErrorCode::NullPtr);
-    let password = get_string!(pass);
+    let password = Zeroizing::new(get_string!(pass));
     if dest.is_null() {
         return ErrorCode::NullPtr;
     }
@@ -298,7 +299,7 @@ pub extern "C" fn libreauth_pass_is_valid_xhmac(
     key: *const u8,
     key_len: libc::size_t,
 ) -> i32 {
-    let p = unsafe { get_string!(pass) };
+    let p = Zeroizing::new(unsafe { get_string!(pass) });
     let r = unsafe { get_string!(reference) };
     let checker = if !key.is_null() {
         let k = unsafe { get_slice!(key, key_len) };
@@ -318,3 +319,124 @@ pub extern "C" fn libreauth_pass_is_valid_xhmac(
         0
     }
 }
+
+/// [C binding] Hash a password using the default configuration of the given algorithm and stores
+/// it in the supplied buffer.
+///
+/// Unlike `libreauth_pass_hash`, the password does not have to be null-terminated.
+///
+/// # Parameters
+///
+/// - `algorithm`: [algorithm](./enum.Algorithm.html#c-interface) to use
+/// - `password`: password to hash
+/// - `password_len`: password's length, in bytes
+/// - `out`: buffer that will hold the null-terminated string representing the hash according
+///   LibreAuth's PHC notation
+/// - `out_len`: buffer's size, in bytes
+///
+/// # Examples
+///
+/// ```c
+/// const char password[] = "correct horse battery staple";
+/// char storage[LIBREAUTH_PASSWORD_STORAGE_LEN];
+///
+/// libreauth_pass_errno ret = libreauth_password_hash(LIBREAUTH_PASS_ARGON2, password, strlen(password), storage, sizeof(storage));
+/// if (ret != LIBREAUTH_PASS_SUCCESS) {
+///     // Handle the error.
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_password_hash(
+    algorithm: Algorithm,
+    password: *const u8,
+    password_len: libc::size_t,
+    out: *mut u8,
+    out_len: libc::size_t,
+) -> ErrorCode {
+    if password.is_null() || out.is_null() {
+        return ErrorCode::NullPtr;
+    }
+    let pass_bytes = Zeroizing::new(get_slice!(password, password_len));
+    let password = match std::str::from_utf8(&pass_bytes) {
+        Ok(p) => p,
+        Err(_) => return ErrorCode::InvalidPasswordFormat,
+    };
+    let buff = get_slice_mut!(out, out_len);
+    let hasher = match HashBuilder::new().algorithm(algorithm).finalize() {
+        Ok(h) => h,
+        Err(e) => return e,
+    };
+    match hasher.hash(password) {
+        Ok(h) => {
+            let b = h.into_bytes();
+            let len = b.len();
+            if len >= out_len {
+                return ErrorCode::NotEnoughSpace;
+            }
+            buff[..len].clone_from_slice(&b);
+            buff[len] = 0;
+            ErrorCode::Success
+        }
+        Err(e) => e,
+    }
+}
+
+/// [C binding] Check whether or not the supplied password is valid.
+///
+/// Unlike `libreauth_pass_is_valid`, neither the hash nor the password have to be
+/// null-terminated.
+///
+/// # Parameters
+///
+/// - `hash`: string representing a previously hashed password using LibreAuth's PHC notation
+/// - `hash_len`: hash's length, in bytes
+/// - `password`: password to check
+/// - `password_len`: password's length, in bytes
+///
+/// # Examples
+///
+/// ```c
+/// if (libreauth_password_verify(storage, strlen(storage), password, strlen(password))) {
+///     printf("Valid password\n");
+/// } else {
+///     printf("Invalid password\n");
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_password_verify(
+    hash: *const u8,
+    hash_len: libc::size_t,
+    password: *const u8,
+    password_len: libc::size_t,
+) -> i32 {
+    if hash.is_null() || password.is_null() {
+        return 0;
+    }
+    let hash = match String::from_utf8(get_slice!(hash, hash_len)) {
+        Ok(h) => h,
+        Err(_) => return 0,
+    };
+    let pass_bytes = Zeroizing::new(get_slice!(password, password_len));
+    let password = match std::str::from_utf8(&pass_bytes) {
+        Ok(p) => p,
+        Err(_) => return 0,
+    };
+    match HashBuilder::from_phc(&hash) {
+        Ok(checker) => {
+            if checker.is_valid(password) {
+                1
+            } else {
+                0
+            }
+        }
+        Err(_) => 0,
+    }
+}
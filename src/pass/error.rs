@@ -46,7 +46,7 @@
 ///     </tbody>
 /// </table>
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum ErrorCode {
     /// Used in C-bindings to indicate the absence of errors.
     Success = 0,
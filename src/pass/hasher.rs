@@ -11,6 +11,7 @@ use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512Trunc224, Sha512Trunc256};
 use sha3::{Keccak224, Keccak256, Keccak384, Keccak512, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
 use std::collections::HashMap;
 use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, Zeroizing};
 
 macro_rules! get_hmac {
     ($hash_func: ty, $salt: ident, $pass: ident) => {{
@@ -108,7 +109,7 @@ impl Hasher {
     }
 
     fn do_hash(&self, password: &str) -> Result<HashedDuo, ErrorCode> {
-        let norm_pass = self.normalize_password(password);
+        let norm_pass = Zeroizing::new(self.normalize_password(password));
         match self.check_password(&norm_pass) {
             Ok(_) => {}
             Err(e) => {
@@ -116,8 +117,8 @@ impl Hasher {
             }
         };
         let norm_pass = match &self.xhmac {
-            XHMAC::Before(salt) => self.apply_xhmac(password.as_bytes(), &salt)?,
-            _ => norm_pass.into_bytes(),
+            XHMAC::Before(salt) => Zeroizing::new(self.apply_xhmac(password.as_bytes(), &salt)?),
+            _ => Zeroizing::new(norm_pass.as_bytes().to_vec()),
         };
         let hash_func = self.get_hash_func();
         let hash = hash_func.hash(&norm_pass);
@@ -160,6 +161,37 @@ impl Hasher {
         Ok(self.do_hash(password)?.formated)
     }
 
+    /// Hash a password stored in a mutable buffer and zero the buffer before returning, whether
+    /// the hashing succeeded or not.
+    ///
+    /// The password must be valid UTF-8, otherwise an `InvalidPasswordFormat` error is returned.
+    /// The internal copies of the password are zeroed as well, however the caller remains
+    /// responsible for any other copy it may have made, including stack-allocated buffers. See
+    /// [SecretString][1] for an owned password that is zeroed on drop.
+    ///
+    /// [1]: struct.SecretString.html
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use libreauth::pass::HashBuilder;
+    ///
+    /// let mut password = b"correct horse battery staple".to_vec();
+    /// let hasher = HashBuilder::new().finalize().unwrap();
+    /// let stored_password = hasher.hash_bytes(&mut password).unwrap();
+    /// assert!(password.iter().all(|&b| b == 0));
+    ///
+    /// let checker = HashBuilder::from_phc(stored_password.as_str()).unwrap();
+    /// assert!(checker.is_valid("correct horse battery staple"));
+    /// ```
+    pub fn hash_bytes(&self, password: &mut [u8]) -> Result<String, ErrorCode> {
+        let ret = match std::str::from_utf8(password) {
+            Ok(p) => self.hash(p),
+            Err(_) => Err(ErrorCode::InvalidPasswordFormat),
+        };
+        password.zeroize();
+        ret
+    }
+
     pub fn is_valid(&self, password: &str) -> bool {
         match self.ref_hash {
             Some(ref rh) => match self.do_hash(password) {
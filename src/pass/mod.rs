@@ -164,6 +164,7 @@ mod hash_builder;
 mod hasher;
 pub(crate) mod pbkdf2;
 mod phc;
+mod secret;
 pub(crate) mod std_default;
 pub(crate) mod std_nist;
 #[cfg(test)]
@@ -180,12 +181,17 @@ pub use self::cbindings::libreauth_pass_init_std;
 #[cfg(feature = "cbindings")]
 pub use self::cbindings::libreauth_pass_is_valid;
 #[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_password_hash;
+#[cfg(feature = "cbindings")]
+pub use self::cbindings::libreauth_password_verify;
+#[cfg(feature = "cbindings")]
 pub use self::cbindings::PassCfg;
 #[cfg(feature = "cbindings")]
 pub use self::cbindings::XHMACType;
 pub use error::ErrorCode;
 pub use hash_builder::HashBuilder;
 pub use hasher::Hasher;
+pub use secret::SecretString;
 use std::collections::HashMap;
 use std::fmt;
 
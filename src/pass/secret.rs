@@ -0,0 +1,64 @@
+use std::fmt;
+use std::ops::Deref;
+use zeroize::Zeroize;
+
+/// A password string which is zeroed in memory when dropped.
+///
+/// Since it dereferences to `str`, it can be used anywhere a password is expected. Please note
+/// that only the memory owned by this structure is zeroed: any copy made outside of it, such as
+/// the buffer it has been read from, has to be zeroed by the caller.
+///
+/// ## Examples
+/// ```rust
+/// use libreauth::pass::{HashBuilder, SecretString};
+///
+/// let password = SecretString::new("correct horse battery staple".to_string());
+/// let hasher = HashBuilder::new().finalize().unwrap();
+/// let stored_password = hasher.hash(&password).unwrap();
+///
+/// let checker = HashBuilder::from_phc(stored_password.as_str()).unwrap();
+/// assert!(checker.is_valid(&password));
+/// ```
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: String) -> Self {
+        SecretString(secret)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        SecretString::new(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        SecretString::new(secret.to_string())
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretString(***)")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
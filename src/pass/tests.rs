@@ -1,6 +1,7 @@
 use super::{
-    std_default, std_nist, Algorithm, HashBuilder, LengthCalculationMethod, Normalization,
-    PasswordStorageStandard, DEFAULT_USER_VERSION, INTERNAL_VERSION, XHMAC,
+    std_default, std_nist, Algorithm, ErrorCode, HashBuilder, LengthCalculationMethod,
+    Normalization, PasswordStorageStandard, SecretString, DEFAULT_USER_VERSION, INTERNAL_VERSION,
+    XHMAC,
 };
 use crate::hash::HashFunction;
 
@@ -367,3 +368,42 @@ fn test_xhmac_no_salt_create() {
     let checker = HashBuilder::from_phc_xhmac(hpass.as_str(), extra_salt).unwrap();
     assert!(!checker.is_valid(password));
 }
+
+#[test]
+fn test_hash_bytes() {
+    let password = "correct horse battery staple";
+    let mut buff = password.as_bytes().to_vec();
+    let hasher = HashBuilder::new().finalize().unwrap();
+    let hpass = hasher.hash_bytes(&mut buff).unwrap();
+    assert!(buff.iter().all(|&b| b == 0));
+    let checker = HashBuilder::from_phc(hpass.as_str()).unwrap();
+    assert!(checker.is_valid(password));
+}
+
+#[test]
+fn test_hash_bytes_error() {
+    let hasher = HashBuilder::new().finalize().unwrap();
+    let mut buff = b"short".to_vec();
+    assert_eq!(
+        hasher.hash_bytes(&mut buff),
+        Err(ErrorCode::PasswordTooShort)
+    );
+    assert!(buff.iter().all(|&b| b == 0));
+    let mut buff = vec![0x63, 0x6f, 0x72, 0xff, 0x65, 0x63, 0x74, 0x20, 0x68];
+    match hasher.hash_bytes(&mut buff) {
+        Err(ErrorCode::InvalidPasswordFormat) => {}
+        _ => panic!("an invalid UTF-8 password has been hashed"),
+    }
+    assert!(buff.iter().all(|&b| b == 0));
+}
+
+#[test]
+fn test_secret_string() {
+    let password = SecretString::from("correct horse battery staple");
+    assert_eq!(&*password, "correct horse battery staple");
+    assert!(!format!("{:?}", password).contains("horse"));
+    let hasher = HashBuilder::new().finalize().unwrap();
+    let hpass = hasher.hash(&password).unwrap();
+    let checker = HashBuilder::from_phc(hpass.as_str()).unwrap();
+    assert!(checker.is_valid(&password));
+}
@@ -0,0 +1,42 @@
+use crate::get_slice_mut;
+use crate::oath::ErrorCode;
+
+/// [C binding] Write the version of the library, e.g. `0.14.0`, in the supplied buffer.
+///
+/// # Parameters
+///
+/// - `out`: buffer that will hold the null-terminated version string
+/// - `out_len`: buffer's size, in bytes
+///
+/// # Return code
+///
+/// `LIBREAUTH_OATH_SUCCESS` in case of success, `LIBREAUTH_OATH_NULL_PTR` if `out` is null and
+/// `LIBREAUTH_OATH_NOT_ENOUGH_SPACE` if the buffer is too small.
+///
+/// ## Examples
+/// ```c
+/// char version[32] = {0};
+/// int32_t ret = libreauth_version(version, sizeof(version));
+/// if (ret != LIBREAUTH_OATH_SUCCESS) {
+///     // Handle the error.
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is a C binding and is therefore unsafe. It is not meant to be used in Rust.
+#[no_mangle]
+pub unsafe extern "C" fn libreauth_version(out: *mut u8, out_len: libc::size_t) -> i32 {
+    if out.is_null() {
+        return ErrorCode::NullPtr as i32;
+    }
+    let version = env!("CARGO_PKG_VERSION").as_bytes();
+    let len = version.len();
+    if len >= out_len {
+        return ErrorCode::NotEnoughSpace as i32;
+    }
+    let buff = get_slice_mut!(out, out_len);
+    buff[..len].clone_from_slice(version);
+    buff[len] = 0;
+    ErrorCode::Success as i32
+}
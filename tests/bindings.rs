@@ -0,0 +1,86 @@
+// The C and Python harnesses exercise the C bindings the way foreign code does. This test builds
+// and runs them against the library cargo has just built, so `cargo test --all-features` covers
+// them too. Each harness is skipped when no C compiler or Python interpreter is available.
+
+#![cfg(all(
+    feature = "cbindings",
+    feature = "key",
+    feature = "oath",
+    feature = "pass"
+))]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// Integration tests are built in `target/<profile>/deps` and the library is copied in the parent
+// directory.
+fn lib_dir() -> PathBuf {
+    let mut dir = env::current_exe().unwrap();
+    dir.pop();
+    dir.pop();
+    dir
+}
+
+fn is_available(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+fn find_program(var: &str, defaults: &[&str]) -> Option<String> {
+    env::var(var)
+        .ok()
+        .into_iter()
+        .chain(defaults.iter().map(|p| p.to_string()))
+        .find(|p| is_available(p))
+}
+
+#[test]
+fn test_c_harness() {
+    let cc = match find_program("CC", &["clang", "gcc", "cc"]) {
+        Some(cc) if is_available("make") => cc,
+        _ => {
+            eprintln!("no C compiler or make available, skipping the C harness");
+            return;
+        }
+    };
+    let status = Command::new("make")
+        .arg("-C")
+        .arg(tests_dir())
+        .arg("clean")
+        .arg("test")
+        .arg(format!("CC={}", cc))
+        .arg(format!("LIBDIR={}/", lib_dir().display()))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_harness() {
+    let python = match find_program("PYTHON", &["python3"]) {
+        Some(python) => python,
+        None => {
+            eprintln!("no Python interpreter available, skipping the Python harness");
+            return;
+        }
+    };
+    let lib = lib_dir().join(format!(
+        "{}libreauth{}",
+        env::consts::DLL_PREFIX,
+        env::consts::DLL_SUFFIX
+    ));
+    let status = Command::new(python)
+        .arg(tests_dir().join("python").join("test_libreauth.py"))
+        .env("LIBREAUTH_LIB", lib)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,264 @@
+// The C header is written by hand since the names it exposes (libreauth_oath_errno,
+// LIBREAUTH_OATH_*, ...) differ from the Rust ones and both the oath and pass modules define an
+// `ErrorCode` type, which generators such as cbindgen cannot tell apart. Generating it would also
+// require a build dependency that does not support the minimum Rust version of this crate. Instead,
+// these tests parse both sides and catch drift in the function list, the layout of the
+// configuration structures and the values of the enums.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+const CBINDINGS: &[&str] = &[
+    "src/key/cbindings.rs",
+    "src/oath/cbindings.rs",
+    "src/pass/cbindings.rs",
+    "src/version.rs",
+];
+const HEADER: &str = "include/libreauth.h";
+
+// (Rust file, Rust struct, C struct)
+const STRUCTS: &[(&str, &str, &str)] = &[
+    ("src/oath/cbindings.rs", "HOTPcfg", "libreauth_hotp_cfg"),
+    ("src/oath/cbindings.rs", "TOTPcfg", "libreauth_totp_cfg"),
+    ("src/pass/cbindings.rs", "PassCfg", "libreauth_pass_cfg"),
+];
+
+// (Rust type, C type)
+const TYPES: &[(&str, &str)] = &[
+    ("*const u8", "const void *"),
+    ("*const libc::c_char", "const char *"),
+    ("*const libc::c_char", "const void *"),
+    ("libc::size_t", "size_t"),
+    ("u32", "uint32_t"),
+    ("u64", "uint64_t"),
+    ("i64", "int64_t"),
+    ("HashFunction", "libreauth_hash_function"),
+    ("Algorithm", "libreauth_pass_algo"),
+    ("LengthCalculationMethod", "libreauth_pass_len_calc"),
+    ("Normalization", "libreauth_pass_normalization"),
+    ("PasswordStorageStandard", "libreauth_pass_standard"),
+    ("XHMACType", "libreauth_pass_xhmac"),
+];
+
+// (Rust file, Rust enum, C enum, C prefix)
+//
+// `libreauth_pass_normalization` is left out since `Normalization::None` is exposed as
+// `LIBREAUTH_PASS_NO_NORMALIZATION`.
+const ENUMS: &[(&str, &str, &str, &str)] = &[
+    (
+        "src/hash.rs",
+        "HashFunction",
+        "libreauth_hash_function",
+        "LIBREAUTH_HASH_",
+    ),
+    (
+        "src/oath/mod.rs",
+        "ErrorCode",
+        "libreauth_oath_errno",
+        "LIBREAUTH_OATH_",
+    ),
+    (
+        "src/pass/error.rs",
+        "ErrorCode",
+        "libreauth_pass_errno",
+        "LIBREAUTH_PASS_",
+    ),
+    (
+        "src/pass/mod.rs",
+        "Algorithm",
+        "libreauth_pass_algo",
+        "LIBREAUTH_PASS_",
+    ),
+    (
+        "src/pass/mod.rs",
+        "LengthCalculationMethod",
+        "libreauth_pass_len_calc",
+        "LIBREAUTH_PASS_",
+    ),
+    (
+        "src/pass/mod.rs",
+        "PasswordStorageStandard",
+        "libreauth_pass_standard",
+        "LIBREAUTH_PASS_",
+    ),
+    (
+        "src/pass/cbindings.rs",
+        "XHMACType",
+        "libreauth_pass_xhmac",
+        "LIBREAUTH_PASS_XHMAC_",
+    ),
+];
+
+fn read(path: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap()
+}
+
+fn identifiers_before(content: &str, pattern: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let end = line.find(pattern)?;
+            let start = line[..end]
+                .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            Some(line[start..end].to_string())
+        })
+        .filter(|name| name.starts_with("libreauth_"))
+        .collect()
+}
+
+fn rust_functions() -> BTreeSet<String> {
+    CBINDINGS
+        .iter()
+        .map(|path| read(path))
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| {
+                    let name = line.split("extern \"C\" fn ").nth(1)?;
+                    Some(name.split('(').next()?.trim().to_string())
+                })
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+fn header_functions() -> BTreeSet<String> {
+    identifiers_before(&read(HEADER), "(")
+}
+
+// Returns the lines between `start` and the next closing brace, without comments and attributes.
+fn block(content: &str, start: &str) -> Vec<String> {
+    content
+        .lines()
+        .skip_while(|line| line.trim() != start)
+        .skip(1)
+        .take_while(|line| !line.trim().starts_with('}'))
+        .map(|line| line.split("//").next().unwrap().trim().to_string())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+// C enums are anonymous and named by their typedef, hence the block is searched backwards.
+fn header_enum_block(name: &str) -> Vec<String> {
+    let header = read(HEADER);
+    let mut lines: Vec<&str> = header
+        .lines()
+        .rev()
+        .skip_while(|line| line.trim() != format!("}} {};", name))
+        .skip(1)
+        .take_while(|line| line.trim() != "typedef enum {")
+        .collect();
+    lines.reverse();
+    block(&format!("{{\n{}\n}}", lines.join("\n")), "{")
+}
+
+fn rust_fields(path: &str, name: &str) -> Vec<(String, String)> {
+    block(&read(path), &format!("pub struct {} {{", name))
+        .iter()
+        .map(|line| {
+            let mut field = line.trim_end_matches(',').splitn(2, ':');
+            let name = field.next().unwrap().trim().to_string();
+            (name, field.next().unwrap().trim().to_string())
+        })
+        .collect()
+}
+
+fn header_fields(name: &str) -> Vec<(String, String)> {
+    block(&read(HEADER), &format!("struct {} {{", name))
+        .iter()
+        .map(|line| {
+            let tokens: Vec<&str> = line.trim_end_matches(';').split_whitespace().collect();
+            let (name, ty) = tokens.split_last().unwrap();
+            let mut ty = ty.join(" ");
+            if name.starts_with('*') {
+                ty.push_str(" *");
+            }
+            (name.trim_start_matches('*').to_string(), ty)
+        })
+        .collect()
+}
+
+// Variants are compared once upper-cased and stripped of underscores, so `InvalidUTF8` matches
+// `INVALID_UTF8` and `Sha3_224` matches `SHA_3_224`.
+fn enum_values(lines: &[String], prefix: &str) -> BTreeMap<String, i64> {
+    lines
+        .iter()
+        .map(|line| {
+            let mut variant = line.trim_end_matches(',').splitn(2, '=');
+            let name = variant.next().unwrap().trim().trim_start_matches(prefix);
+            let value = variant.next().unwrap().trim().parse().unwrap();
+            (name.replace('_', "").to_uppercase(), value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_header_declares_all_functions() {
+    let missing: Vec<String> = rust_functions()
+        .difference(&header_functions())
+        .cloned()
+        .collect();
+    assert!(
+        missing.is_empty(),
+        "functions missing from {}: {:?}",
+        HEADER,
+        missing
+    );
+}
+
+#[test]
+fn test_header_functions_exist() {
+    let unknown: Vec<String> = header_functions()
+        .difference(&rust_functions())
+        .cloned()
+        .collect();
+    assert!(
+        unknown.is_empty(),
+        "functions declared in {} but not defined: {:?}",
+        HEADER,
+        unknown
+    );
+}
+
+#[test]
+fn test_header_struct_layouts() {
+    for (path, rust_name, c_name) in STRUCTS {
+        let rust = rust_fields(path, rust_name);
+        let c = header_fields(c_name);
+        assert!(
+            !rust.is_empty(),
+            "struct {} not found in {}",
+            rust_name,
+            path
+        );
+        let rust_names: Vec<&String> = rust.iter().map(|(name, _)| name).collect();
+        let c_names: Vec<&String> = c.iter().map(|(name, _)| name).collect();
+        assert_eq!(rust_names, c_names, "fields of struct {}", c_name);
+        for ((name, rust_ty), (_, c_ty)) in rust.iter().zip(c.iter()) {
+            assert!(
+                TYPES.contains(&(rust_ty.as_str(), c_ty.as_str())),
+                "field {}.{}: {} does not match {}",
+                c_name,
+                name,
+                c_ty,
+                rust_ty
+            );
+        }
+    }
+}
+
+#[test]
+fn test_header_enum_values() {
+    for (path, rust_name, c_name, prefix) in ENUMS {
+        let rust = enum_values(
+            &block(&read(path), &format!("pub enum {} {{", rust_name)),
+            "",
+        );
+        let c = enum_values(&header_enum_block(c_name), prefix);
+        assert!(!rust.is_empty(), "enum {} not found in {}", rust_name, path);
+        assert_eq!(rust, c, "values of enum {}", c_name);
+    }
+}
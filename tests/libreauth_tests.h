@@ -40,3 +40,4 @@ uint32_t    test_hotp(void);
 uint32_t    test_totp(void);
 uint32_t    test_pass(void);
 uint32_t    test_key(void);
+uint32_t    test_version(void);
@@ -0,0 +1,88 @@
+#![cfg(feature = "oath")]
+
+use libreauth::hash::HashFunction;
+use libreauth::oath::{HOTPBuilder, TOTPBuilder};
+use proptest::prelude::*;
+use std::collections::HashSet;
+
+const HASH_FUNCTIONS: [HashFunction; 5] = [
+    HashFunction::Sha1,
+    HashFunction::Sha256,
+    HashFunction::Sha512,
+    HashFunction::Sha3_256,
+    HashFunction::Keccak512,
+];
+
+fn key() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 16..64)
+}
+
+proptest! {
+    #[test]
+    fn hotp_generate_is_valid(key in key(), counter in any::<u64>(), output_len in 6..10usize, hash_function in 0..HASH_FUNCTIONS.len()) {
+        let hotp = HOTPBuilder::new()
+            .key(&key)
+            .counter(counter)
+            .output_len(output_len)
+            .hash_function(HASH_FUNCTIONS[hash_function])
+            .finalize()
+            .unwrap();
+        let code = hotp.generate();
+        prop_assert_eq!(code.len(), output_len);
+        prop_assert!(hotp.is_valid(&code));
+    }
+
+    #[test]
+    fn totp_generate_is_valid(key in key(), timestamp in 0..i64::from(u32::max_value()), period in 1..3600u32, hash_function in 0..HASH_FUNCTIONS.len()) {
+        let totp = TOTPBuilder::new()
+            .key(&key)
+            .timestamp(timestamp)
+            .period(period)
+            .hash_function(HASH_FUNCTIONS[hash_function])
+            .finalize()
+            .unwrap();
+        let code = totp.generate();
+        prop_assert!(totp.is_valid(&code));
+    }
+
+    #[test]
+    fn base32_key_generate_is_valid(key in key(), counter in any::<u32>()) {
+        let key_base32 = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &key);
+        let hotp = HOTPBuilder::new()
+            .base32_key(&key_base32)
+            .counter(u64::from(counter))
+            .finalize()
+            .unwrap();
+        let reference = HOTPBuilder::new()
+            .key(&key)
+            .counter(u64::from(counter))
+            .finalize()
+            .unwrap();
+        let code = hotp.generate();
+        prop_assert_eq!(&code, &reference.generate());
+        prop_assert!(reference.is_valid(&code));
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    // With 10^8 possible codes, the expected number of collisions among 1000 codes is 0.005, so
+    // more than a handful is an indication of a bias.
+    #[test]
+    fn hotp_counters_collisions(key in key(), start in 0..u64::from(u32::max_value())) {
+        let nb_codes = 1000;
+        let codes: HashSet<String> = (start..start + nb_codes)
+            .map(|counter| {
+                HOTPBuilder::new()
+                    .key(&key)
+                    .counter(counter)
+                    .output_len(8)
+                    .finalize()
+                    .unwrap()
+                    .generate()
+            })
+            .collect();
+        prop_assert!(codes.len() as u64 >= nb_codes - 5);
+    }
+}
@@ -101,6 +101,76 @@ static uint32_t test_basic_key_uri(void) {
     return 1;
 }
 
+static uint32_t test_generate_n(void) {
+    test_name("hotp: test_generate_n");
+
+    struct libreauth_hotp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    const char *expected[] = {"755224", "287082", "359152", "969429", "338314"};
+    char buff[5][DEFAULT_BUFF_LEN + 1];
+    char *codes[5] = {buff[0], buff[1], buff[2], buff[3], buff[4]};
+    uint32_t ret;
+
+    libreauth_hotp_init(&cfg);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    ret = libreauth_hotp_generate_n(&cfg, codes, DEFAULT_BUFF_LEN + 1, 5);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    for (int i = 0; i < 5; ++i) {
+        assert(strncmp(codes[i], expected[i], DEFAULT_BUFF_LEN + 1) == 0);
+    }
+
+    cfg.counter = 3;
+    ret = libreauth_hotp_generate_n(&cfg, codes, DEFAULT_BUFF_LEN + 1, 2);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strncmp(codes[0], expected[3], DEFAULT_BUFF_LEN + 1) == 0);
+    assert(strncmp(codes[1], expected[4], DEFAULT_BUFF_LEN + 1) == 0);
+
+    ret = libreauth_hotp_generate_n(&cfg, codes, DEFAULT_BUFF_LEN, 5);
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+
+    ret = libreauth_hotp_generate_n(NULL, codes, DEFAULT_BUFF_LEN + 1, 5);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    ret = libreauth_hotp_generate_n(&cfg, NULL, DEFAULT_BUFF_LEN + 1, 5);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    codes[1] = NULL;
+    ret = libreauth_hotp_generate_n(&cfg, codes, DEFAULT_BUFF_LEN + 1, 5);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    return 1;
+}
+
+static uint32_t test_rfc4226_vectors(void) {
+    test_name("hotp: test_rfc4226_vectors");
+
+    struct libreauth_hotp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    const char *codes[] = {
+        "755224", "287082", "359152", "969429", "338314",
+        "254676", "287922", "162583", "399871", "520489",
+    };
+    char code[DEFAULT_BUFF_LEN + 1];
+
+    uint32_t ret = libreauth_hotp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    for (uint64_t i = 0; i < sizeof(codes) / sizeof(codes[0]); ++i) {
+        cfg.counter = i;
+        ret = libreauth_hotp_generate(&cfg, code);
+        assert(ret == LIBREAUTH_OATH_SUCCESS);
+        assert(strncmp(code, codes[i], DEFAULT_BUFF_LEN + 1) == 0);
+        assert(libreauth_hotp_is_valid(&cfg, codes[i]));
+        assert(!libreauth_hotp_is_valid(&cfg, codes[(i + 1) % 10]));
+    }
+
+    return 1;
+}
+
 static uint32_t test_init_null_ptr(void) {
     test_name("hotp: test_init_null_ptr");
 
@@ -110,6 +180,54 @@ static uint32_t test_init_null_ptr(void) {
     return 1;
 }
 
+static uint32_t test_init_overwrite(void) {
+    test_name("hotp: test_init_overwrite");
+
+    struct libreauth_hotp_cfg cfg;
+    memset(&cfg, 0xff, sizeof(cfg));
+
+    uint32_t ret = libreauth_hotp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(cfg.key == NULL);
+    assert(cfg.key_len == 0);
+    assert(cfg.counter == 0);
+    assert(cfg.output_len == DEFAULT_BUFF_LEN);
+    assert(cfg.output_base == NULL);
+    assert(cfg.hash_function == LIBREAUTH_HASH_SHA_1);
+
+    return 1;
+}
+
+static uint32_t test_counter_set(void) {
+    test_name("hotp: test_counter_set");
+
+    struct libreauth_hotp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    char code[DEFAULT_BUFF_LEN + 1];
+
+    uint32_t ret = libreauth_hotp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    ret = libreauth_hotp_counter_set(&cfg, 42);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(cfg.counter == 42);
+    assert(cfg.key == key);
+    assert(cfg.output_len == DEFAULT_BUFF_LEN);
+
+    ret = libreauth_hotp_counter_set(&cfg, 1);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    ret = libreauth_hotp_generate(&cfg, code);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strncmp(code, "287082", DEFAULT_BUFF_LEN + 1) == 0);
+
+    ret = libreauth_hotp_counter_set(NULL, 42);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    return 1;
+}
+
 static uint32_t test_generate_null_ptr(void) {
     test_name("hotp: test_generate_null_ptr");
 
@@ -259,15 +377,112 @@ static uint32_t test_invalid_code(void) {
     return 1;
 }
 
+static uint32_t test_to_uri(void) {
+    test_name("hotp: test_to_uri");
+
+    struct libreauth_hotp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    const char issuer[] = "Provider1";
+    const char account[] = "alice@example.com";
+    const char expected[] = "otpauth://hotp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1&counter=42";
+    char uri_buff[DEFAULT_URI_BUFF_LEN + 1];
+
+    uint32_t ret = libreauth_hotp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+    cfg.counter = 42;
+
+    ret = libreauth_hotp_to_uri(NULL, issuer, strlen(issuer), account, strlen(account), uri_buff, sizeof(uri_buff));
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_hotp_to_uri(&cfg, issuer, strlen(issuer), NULL, 0, uri_buff, sizeof(uri_buff));
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_hotp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), NULL, 42);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_hotp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), uri_buff, strlen(expected));
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+
+    ret = libreauth_hotp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), uri_buff, strlen(expected) + 1);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strcmp(uri_buff, expected) == 0);
+
+    return 1;
+}
+
+static uint32_t test_is_valid_at_counter(void) {
+    test_name("hotp: test_is_valid_at_counter");
+
+    struct libreauth_hotp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    const char *codes[] = {"755224", "287082", "359152", "969429", "338314"};
+
+    uint32_t ret = libreauth_hotp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    for (uint64_t counter = 0; counter < 5; ++counter) {
+        assert(libreauth_hotp_is_valid_at_counter(&cfg, codes[counter], 6, counter) == 1);
+        assert(libreauth_hotp_is_valid_at_counter(&cfg, codes[counter], 6, counter + 1) == 0);
+        assert(cfg.counter == 0);
+    }
+    assert(libreauth_hotp_is_valid_at_counter(&cfg, "2870829", 6, 1) == 1);
+    assert(libreauth_hotp_is_valid_at_counter(&cfg, "28708", 5, 1) == 0);
+
+    assert(libreauth_hotp_is_valid_at_counter(NULL, "287082", 6, 1) == -LIBREAUTH_OATH_NULL_PTR);
+    assert(libreauth_hotp_is_valid_at_counter(&cfg, NULL, 6, 1) == -LIBREAUTH_OATH_NULL_PTR);
+    assert(libreauth_hotp_is_valid_at_counter(&cfg, "\xff", 1, 1) == -LIBREAUTH_OATH_INVALID_UTF8);
+    cfg.key = NULL;
+    assert(libreauth_hotp_is_valid_at_counter(&cfg, "287082", 6, 1) == -LIBREAUTH_OATH_NULL_PTR);
+
+    return 1;
+}
+
+static uint32_t test_generate_with_len(void) {
+    test_name("hotp: test_generate_with_len");
+
+    struct libreauth_hotp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    char code[] = "qwertyuiop";
+
+    libreauth_hotp_init(&cfg);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    uint32_t ret = libreauth_hotp_generate_with_len(&cfg, code, DEFAULT_BUFF_LEN);
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    assert(strcmp(code, "qwertyuiop") == 0);
+
+    ret = libreauth_hotp_generate_with_len(&cfg, code, 0);
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    assert(strcmp(code, "qwertyuiop") == 0);
+
+    ret = libreauth_hotp_generate_with_len(&cfg, NULL, DEFAULT_BUFF_LEN + 1);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    ret = libreauth_hotp_generate_with_len(&cfg, code, DEFAULT_BUFF_LEN + 1);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strcmp(code, "755224") == 0);
+
+    return 1;
+}
+
 uint32_t test_hotp(void) {
     uint32_t nb_tests = 0;
 
     nb_tests += test_basic_hotp();
     nb_tests += test_basic_key_uri();
+    nb_tests += test_generate_n();
+    nb_tests += test_rfc4226_vectors();
     nb_tests += test_init_null_ptr();
+    nb_tests += test_init_overwrite();
+    nb_tests += test_counter_set();
     nb_tests += test_generate_null_ptr();
+    nb_tests += test_generate_with_len();
     nb_tests += test_invalid_base();
     nb_tests += test_invalid_code();
+    nb_tests += test_to_uri();
+    nb_tests += test_is_valid_at_counter();
 
     return nb_tests;
 }
@@ -74,12 +74,31 @@ static uint32_t test_zero_len(void) {
     return 1;
 }
 
+static uint32_t test_random_key(void) {
+    test_name("key: test_random_key");
+
+    uint8_t k1[KEY_SIZE] = {0};
+    uint8_t k2[KEY_SIZE] = {0};
+
+    assert(libreauth_random_key(KEY_SIZE, k1) == 0);
+    assert(libreauth_random_key(KEY_SIZE, k2) == 0);
+    assert(memcmp(k1, k2, KEY_SIZE) != 0);
+
+    assert(libreauth_random_key(16, k1) == 0);
+    assert(libreauth_random_key(15, k1) == -LIBREAUTH_OATH_INVALID_KEY_LEN);
+    assert(libreauth_random_key(0, k1) == -LIBREAUTH_OATH_INVALID_KEY_LEN);
+    assert(libreauth_random_key(KEY_SIZE, NULL) == -LIBREAUTH_OATH_NULL_PTR);
+
+    return 1;
+}
+
 uint32_t test_key(void) {
     int nb_tests = 0;
 
     nb_tests += test_uniqueness();
     nb_tests += test_null_ptr();
     nb_tests += test_zero_len();
+    nb_tests += test_random_key();
 
     return nb_tests;
 }
@@ -49,6 +49,7 @@ int main(void) {
     nb_tests += test_totp();
     nb_tests += test_pass();
     nb_tests += test_key();
+    nb_tests += test_version();
 
     printf("Ran %d tests.\n", nb_tests);
 
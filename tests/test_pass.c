@@ -168,6 +168,43 @@ static uint32_t test_xhmac(void) {
     return 1;
 }
 
+static uint32_t test_password_hash(void) {
+    test_name("pass: test_password_hash");
+
+    const char password[] = "correct horse battery staple",
+          invalid_pass[] = "123456";
+    const libreauth_pass_algo algorithms[] = {LIBREAUTH_PASS_ARGON2, LIBREAUTH_PASS_PBKDF2};
+    char storage[LIBREAUTH_PASSWORD_STORAGE_LEN];
+    libreauth_pass_errno ret;
+
+    for (size_t i = 0; i < sizeof(algorithms) / sizeof(algorithms[0]); ++i) {
+        ret = libreauth_password_hash(algorithms[i], password, strlen(password), storage, LIBREAUTH_PASSWORD_STORAGE_LEN);
+        assert(ret == LIBREAUTH_PASS_SUCCESS);
+        assert(libreauth_pass_is_valid(password, storage));
+        assert(libreauth_password_verify(storage, strlen(storage), password, strlen(password)));
+        assert(!libreauth_password_verify(storage, strlen(storage), invalid_pass, strlen(invalid_pass)));
+        assert(!libreauth_password_verify(storage, strlen(storage), password, strlen(password) - 1));
+        assert(!libreauth_password_verify(storage, strlen(storage) - 1, password, strlen(password)));
+    }
+
+    ret = libreauth_password_hash(LIBREAUTH_PASS_ARGON2, invalid_pass, strlen(invalid_pass), storage, LIBREAUTH_PASSWORD_STORAGE_LEN);
+    assert(ret == LIBREAUTH_PASS_PASSWORD_TOO_SHORT);
+
+    ret = libreauth_password_hash(LIBREAUTH_PASS_ARGON2, password, strlen(password), storage, 10);
+    assert(ret == LIBREAUTH_PASS_NOT_ENOUGH_SPACE);
+
+    ret = libreauth_password_hash(LIBREAUTH_PASS_ARGON2, NULL, 0, storage, LIBREAUTH_PASSWORD_STORAGE_LEN);
+    assert(ret == LIBREAUTH_PASS_NULL_PTR);
+
+    ret = libreauth_password_hash(LIBREAUTH_PASS_ARGON2, password, strlen(password), NULL, 0);
+    assert(ret == LIBREAUTH_PASS_NULL_PTR);
+
+    assert(!libreauth_password_verify(NULL, 0, password, strlen(password)));
+    assert(!libreauth_password_verify(storage, strlen(storage), NULL, 0));
+
+    return 1;
+}
+
 uint32_t test_pass(void) {
     int nb_tests = 0;
 
@@ -175,6 +212,7 @@ uint32_t test_pass(void) {
     nb_tests += test_nist_pass();
     nb_tests += test_invalid_pass();
     nb_tests += test_xhmac();
+    nb_tests += test_password_hash();
 
     return nb_tests;
 }
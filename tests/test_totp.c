@@ -35,6 +35,7 @@
 
 #include <strings.h>
 #include <string.h>
+#include <time.h>
 #include <assert.h>
 #include <libreauth.h>
 #include "libreauth_tests.h"
@@ -136,6 +137,49 @@ static uint32_t test_advanced_totp(void) {
     return 1;
 }
 
+static uint32_t test_rfc6238_vectors(void) {
+    test_name("totp: test_rfc6238_vectors");
+
+    const char key_sha1[] = "12345678901234567890",
+          key_sha256[] = "12345678901234567890123456789012",
+          key_sha512[] = "1234567890123456789012345678901234567890123456789012345678901234";
+    const struct {
+        int64_t timestamp;
+        const char *codes[3];
+    } vectors[] = {
+        {59, {"94287082", "46119246", "90693936"}},
+        {1111111109, {"07081804", "68084774", "25091201"}},
+        {1111111111, {"14050471", "67062674", "99943326"}},
+        {1234567890, {"89005924", "91819424", "93441116"}},
+        {2000000000, {"69279037", "90698825", "38618901"}},
+        {20000000000, {"65353130", "77737706", "47863826"}},
+    };
+    const char *keys[] = {key_sha1, key_sha256, key_sha512};
+    const libreauth_hash_function hash_functions[] = {LIBREAUTH_HASH_SHA_1, LIBREAUTH_HASH_SHA_256, LIBREAUTH_HASH_SHA_512};
+    struct libreauth_totp_cfg cfg;
+    char code[BIGGER_BUFF_LEN + 1];
+
+    for (size_t i = 0; i < sizeof(vectors) / sizeof(vectors[0]); ++i) {
+        for (size_t j = 0; j < 3; ++j) {
+            uint32_t ret = libreauth_totp_init(&cfg);
+            assert(ret == LIBREAUTH_OATH_SUCCESS);
+            cfg.key = keys[j];
+            cfg.key_len = strlen(keys[j]);
+            cfg.timestamp = vectors[i].timestamp;
+            cfg.output_len = BIGGER_BUFF_LEN;
+            cfg.hash_function = hash_functions[j];
+
+            ret = libreauth_totp_generate(&cfg, code);
+            assert(ret == LIBREAUTH_OATH_SUCCESS);
+            assert(strncmp(code, vectors[i].codes[j], BIGGER_BUFF_LEN + 1) == 0);
+            assert(libreauth_totp_is_valid(&cfg, vectors[i].codes[j]));
+            assert(!libreauth_totp_is_valid(&cfg, vectors[(i + 1) % 6].codes[j]));
+        }
+    }
+
+    return 1;
+}
+
 static uint32_t test_tolerance(void) {
     test_name("totp: test_tolerance");
 
@@ -186,6 +230,30 @@ static uint32_t test_init_null_ptr(void) {
     return 1;
 }
 
+static uint32_t test_init_overwrite(void) {
+    test_name("totp: test_init_overwrite");
+
+    struct libreauth_totp_cfg cfg;
+    time_t before = time(NULL);
+    memset(&cfg, 0xff, sizeof(cfg));
+
+    uint32_t ret = libreauth_totp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(cfg.key == NULL);
+    assert(cfg.key_len == 0);
+    /* time() may use a coarse clock lagging behind the one used by the library. */
+    assert(cfg.timestamp >= before && cfg.timestamp <= time(NULL) + 1);
+    assert(cfg.positive_tolerance == 0);
+    assert(cfg.negative_tolerance == 0);
+    assert(cfg.period == 30);
+    assert(cfg.initial_time == 0);
+    assert(cfg.output_len == DEFAULT_BUFF_LEN);
+    assert(cfg.output_base == NULL);
+    assert(cfg.hash_function == LIBREAUTH_HASH_SHA_1);
+
+    return 1;
+}
+
 static uint32_t test_generate_null_ptr(void) {
     test_name("totp: test_generate_null_ptr");
 
@@ -241,16 +309,164 @@ static uint32_t test_invalid_base(void) {
     return 1;
 }
 
+static uint32_t test_generate_for_window(void) {
+    test_name("totp: test_generate_for_window");
+
+    struct libreauth_totp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    char **codes = NULL;
+    uint32_t codes_len = 0;
+
+    libreauth_totp_init(&cfg);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+    cfg.timestamp = 1111111109;
+    cfg.output_len = BIGGER_BUFF_LEN;
+
+    uint32_t ret = libreauth_totp_generate_for_window(&cfg, 1, &codes, &codes_len);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(codes != NULL);
+    assert(codes_len == 3);
+    assert(strcmp(codes[1], "07081804") == 0);
+    assert(strcmp(codes[2], "14050471") == 0);
+    libreauth_free_codes(codes, codes_len);
+
+    ret = libreauth_totp_generate_for_window(NULL, 1, &codes, &codes_len);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_totp_generate_for_window(&cfg, 1, NULL, &codes_len);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_totp_generate_for_window(&cfg, 1, &codes, NULL);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    libreauth_free_codes(NULL, 0);
+
+    return 1;
+}
+
+static uint32_t test_to_uri(void) {
+    test_name("totp: test_to_uri");
+
+    struct libreauth_totp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    const char issuer[] = "Provider1";
+    const char account[] = "alice@example.com";
+    const char expected[] = "otpauth://totp/Provider1:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Provider1";
+    char uri_buff[DEFAULT_URI_BUFF_LEN + 1];
+
+    uint32_t ret = libreauth_totp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    ret = libreauth_totp_to_uri(NULL, issuer, strlen(issuer), account, strlen(account), uri_buff, sizeof(uri_buff));
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_totp_to_uri(&cfg, NULL, 0, account, strlen(account), uri_buff, sizeof(uri_buff));
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_totp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), NULL, 42);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+    ret = libreauth_totp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), uri_buff, strlen(expected));
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    ret = libreauth_totp_to_uri(&cfg, "\xff", 1, account, strlen(account), uri_buff, sizeof(uri_buff));
+    assert(ret == LIBREAUTH_OATH_INVALID_UTF8);
+
+    ret = libreauth_totp_to_uri(&cfg, issuer, strlen(issuer), account, strlen(account), uri_buff, strlen(expected) + 1);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strcmp(uri_buff, expected) == 0);
+
+    /* The names are not required to be null-terminated. */
+    ret = libreauth_totp_to_uri(&cfg, "Provider1234", strlen(issuer), "alice@example.com!", strlen(account), uri_buff, sizeof(uri_buff));
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strcmp(uri_buff, expected) == 0);
+
+    return 1;
+}
+
+static uint32_t test_is_valid_at(void) {
+    test_name("totp: test_is_valid_at");
+
+    struct libreauth_totp_cfg cfg;
+    const char key[] = "12345678901234567890";
+
+    uint32_t ret = libreauth_totp_init(&cfg);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+    cfg.output_len = 8;
+
+    assert(libreauth_totp_is_valid_at(&cfg, "07081804", 8, 1111111109) == 1);
+    assert(libreauth_totp_is_valid_at(&cfg, "07081804", 8, 1111111110) == 0);
+    assert(libreauth_totp_is_valid_at(&cfg, "14050471", 8, 1111111111) == 1);
+    assert(libreauth_totp_is_valid_at(&cfg, "070818049", 8, 1111111109) == 1);
+    assert(libreauth_totp_is_valid_at(&cfg, "0708180", 7, 1111111109) == 0);
+
+    assert(libreauth_totp_is_valid_at(NULL, "07081804", 8, 1111111109) == -LIBREAUTH_OATH_NULL_PTR);
+    assert(libreauth_totp_is_valid_at(&cfg, NULL, 8, 1111111109) == -LIBREAUTH_OATH_NULL_PTR);
+    assert(libreauth_totp_is_valid_at(&cfg, "\xff", 1, 1111111109) == -LIBREAUTH_OATH_INVALID_UTF8);
+    assert(libreauth_totp_is_valid_at(&cfg, "07081804", 8, UINT64_MAX) == -LIBREAUTH_OATH_TIMESTAMP_OVERFLOW);
+    cfg.initial_time = 1111111110;
+    assert(libreauth_totp_is_valid_at(&cfg, "07081804", 8, 1111111109) == -LIBREAUTH_OATH_TIMESTAMP_OVERFLOW);
+    cfg.initial_time = 0;
+
+    cfg.negative_tolerance = 1;
+    assert(libreauth_totp_is_valid_at(&cfg, "84755224", 8, 10) == 1);
+    assert(libreauth_totp_is_valid_at(&cfg, "94287082", 8, 10) == 0);
+    assert(libreauth_totp_is_valid_at(&cfg, "84755224", 8, 35) == 1);
+    assert(libreauth_totp_is_valid_at(&cfg, "94287082", 8, 35) == 1);
+    cfg.negative_tolerance = 0;
+
+    cfg.output_len = 6;
+    cfg.output_base = "0123456789ABCDEF";
+    assert(libreauth_totp_is_valid_at(&cfg, "10F84C", 6, 1111111109) == 1);
+    assert(libreauth_totp_is_valid_at(&cfg, "10F84D", 6, 1111111109) == 0);
+
+    return 1;
+}
+
+static uint32_t test_generate_with_len(void) {
+    test_name("totp: test_generate_with_len");
+
+    struct libreauth_totp_cfg cfg;
+    const char key[] = "12345678901234567890";
+    char code[] = "qwertyuiop";
+
+    libreauth_totp_init(&cfg);
+    cfg.key = key;
+    cfg.key_len = strlen(key);
+
+    uint32_t ret = libreauth_totp_generate_with_len(&cfg, code, DEFAULT_BUFF_LEN);
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    assert(strcmp(code, "qwertyuiop") == 0);
+
+    ret = libreauth_totp_generate_with_len(&cfg, code, 0);
+    assert(ret == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    assert(strcmp(code, "qwertyuiop") == 0);
+
+    ret = libreauth_totp_generate_with_len(&cfg, NULL, DEFAULT_BUFF_LEN + 1);
+    assert(ret == LIBREAUTH_OATH_NULL_PTR);
+
+    ret = libreauth_totp_generate_with_len(&cfg, code, DEFAULT_BUFF_LEN + 1);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strlen(code) == DEFAULT_BUFF_LEN);
+
+    return 1;
+}
+
 uint32_t test_totp(void) {
     uint32_t nb_tests = 0;
 
     nb_tests += test_basic_totp();
     nb_tests += test_basic_key_uri();
     nb_tests += test_advanced_totp();
+    nb_tests += test_rfc6238_vectors();
     nb_tests += test_tolerance();
     nb_tests += test_init_null_ptr();
+    nb_tests += test_init_overwrite();
     nb_tests += test_generate_null_ptr();
+    nb_tests += test_generate_with_len();
     nb_tests += test_invalid_base();
+    nb_tests += test_generate_for_window();
+    nb_tests += test_to_uri();
+    nb_tests += test_is_valid_at();
 
     return nb_tests;
 }
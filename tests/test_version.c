@@ -0,0 +1,92 @@
+/*
+ * Copyright Rodolphe Breard (2020)
+ * Author: Rodolphe Breard (2020)
+ *
+ * This software is a computer library whose purpose is to offer a
+ * collection of tools for user authentication.
+ *
+ * This software is governed by the CeCILL  license under French law and
+ * abiding by the rules of distribution of free software.  You can  use,
+ * modify and/ or redistribute the software under the terms of the CeCILL
+ * license as circulated by CEA, CNRS and INRIA at the following URL
+ * "http://www.cecill.info".
+ *
+ * As a counterpart to the access to the source code and  rights to copy,
+ * modify and redistribute granted by the license, users are provided only
+ * with a limited warranty  and the software's author,  the holder of the
+ * economic rights,  and the successive licensors  have only  limited
+ * liability.
+ *
+ * In this respect, the user's attention is drawn to the risks associated
+ * with loading,  using,  modifying and/or developing or reproducing the
+ * software by the user in light of its specific status of free software,
+ * that may mean  that it is complicated to manipulate,  and  that  also
+ * therefore means  that it is reserved for developers  and  experienced
+ * professionals having in-depth computer knowledge. Users are therefore
+ * encouraged to load and test the software's suitability as regards their
+ * requirements in conditions enabling the security of their systems and/or
+ * data to be ensured and,  more generally, to use and operate it in the
+ * same conditions as regards security.
+ *
+ * The fact that you are presently reading this means that you have had
+ * knowledge of the CeCILL license and that you accept its terms.
+ */
+
+
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <libreauth.h>
+#include "libreauth_tests.h"
+
+#define VERSION_BUFF_LEN 32
+
+static uint32_t test_get_version(void) {
+    test_name("version: test_get_version");
+
+    char version[VERSION_BUFF_LEN] = {0};
+    unsigned int major = 0, minor = 0, patch = 0;
+
+    int32_t ret = libreauth_version(version, VERSION_BUFF_LEN);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+    assert(strlen(version) > 0);
+    assert(sscanf(version, "%u.%u.%u", &major, &minor, &patch) == 3);
+
+    return 1;
+}
+
+static uint32_t test_small_buffer(void) {
+    test_name("version: test_small_buffer");
+
+    char version[VERSION_BUFF_LEN] = {0};
+    int32_t ret = libreauth_version(version, VERSION_BUFF_LEN);
+    assert(ret == LIBREAUTH_OATH_SUCCESS);
+
+    size_t len = strlen(version);
+    char small[VERSION_BUFF_LEN] = {0};
+    assert(libreauth_version(small, len) == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    assert(libreauth_version(small, 0) == LIBREAUTH_OATH_NOT_ENOUGH_SPACE);
+    assert(libreauth_version(small, len + 1) == LIBREAUTH_OATH_SUCCESS);
+    assert(strcmp(small, version) == 0);
+
+    return 1;
+}
+
+static uint32_t test_null_ptr(void) {
+    test_name("version: test_null_ptr");
+
+    assert(libreauth_version(NULL, VERSION_BUFF_LEN) == LIBREAUTH_OATH_NULL_PTR);
+
+    return 1;
+}
+
+uint32_t test_version(void) {
+    int nb_tests = 0;
+
+    nb_tests += test_get_version();
+    nb_tests += test_small_buffer();
+    nb_tests += test_null_ptr();
+
+    return nb_tests;
+}